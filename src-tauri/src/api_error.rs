@@ -0,0 +1,90 @@
+use std::{error::Error, fmt};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// Failure fetching launcher metadata (version lists, manifests...).
+#[derive(Debug)]
+pub enum ApiError {
+    /// Offline mode is on and nothing was cached yet.
+    Offline(String),
+    Timeout(String),
+    Network(String),
+    Http {
+        status: u16,
+        url: String,
+    },
+    InvalidResponse(String),
+}
+
+impl ApiError {
+    fn kind(&self) -> &'static str {
+        match self {
+            ApiError::Offline(_) => "offline",
+            ApiError::Timeout(_) => "timeout",
+            ApiError::Network(_) => "network",
+            ApiError::Http { .. } => "http",
+            ApiError::InvalidResponse(_) => "invalid_response",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Offline(name) => {
+                write!(f, "Modo sin conexión: no hay datos guardados de {}", name)
+            }
+            ApiError::Timeout(e) => write!(f, "El servidor tardó demasiado en responder: {}", e),
+            ApiError::Network(e) => write!(f, "Error de red: {}", e),
+            ApiError::Http { status, url } => {
+                write!(
+                    f,
+                    "El servidor respondió con el código {} ({})",
+                    status, url
+                )
+            }
+            ApiError::InvalidResponse(e) => write!(f, "Respuesta inválida del servidor: {}", e),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+impl Serialize for ApiError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ApiError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field(
+            "status",
+            &match self {
+                ApiError::Http { status, .. } => Some(*status),
+                _ => None,
+            },
+        )?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ApiError::Timeout(e.to_string())
+        } else if let Some(status) = e.status() {
+            ApiError::Http {
+                status: status.as_u16(),
+                url: e.url().map(|url| url.to_string()).unwrap_or_default(),
+            }
+        } else if e.is_decode() {
+            ApiError::InvalidResponse(e.to_string())
+        } else {
+            ApiError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::InvalidResponse(e.to_string())
+    }
+}
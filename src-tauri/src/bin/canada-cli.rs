@@ -0,0 +1,261 @@
+//! Prepares the Canada instance from a terminal, for servers and CI. Uses the
+//! same settings and directories as the app.
+
+use std::{collections::HashMap, env, path::Path, process::ExitCode, sync::Arc};
+
+use canadadowloader_lib::{
+    data_structs::{ModLoaders, VersionType},
+    dowloader::{self, BucketConfig},
+    events::{self, EventSink, StderrSink},
+    launcher_core,
+};
+
+const USAGE: &str = "Uso: canada-cli <comando> [opciones]
+
+Comandos:
+  sync                Sincroniza los mods y paquetes de recursos
+  install             Sincroniza, instala el loader y crea el perfil
+  install-loader      Solo instala el loader
+  create-profile      Crea el perfil de una versión ya instalada
+  list-versions       Lista las versiones de minecraft, fabric o forge
+  launch              Abre el Minecraft Launcher
+  publish <carpeta>   Sube los mods, paquetes de recursos y configs de la
+                      carpeta a los buckets (requiere claves con escritura)
+
+Opciones de install, install-loader y create-profile:
+  --loader <forge|fabric|vanilla>
+  --mc <versión de Minecraft>
+  --version <versión del loader>   (no hace falta con vanilla)
+
+Opciones de list-versions <minecraft|fabric|forge>:
+  --snapshots         Incluye snapshots de Minecraft
+  --mc <versión>      Versión de Minecraft, obligatoria con forge
+
+Opciones de publish:
+  --dry-run           Muestra los cambios sin subir ni borrar nada
+  Las claves con escritura se leen de MODS_R2_ACCESS_KEY, MODS_R2_SECRET_KEY,
+  RESP_R2_ACCESS_KEY y RESP_R2_SECRET_KEY; sin ellas se usan las de la app.
+
+Opciones generales:
+  -h, --help          Muestra esta ayuda
+  -V, --version       Muestra la versión
+
+El progreso se escribe en stderr y los resultados en stdout.";
+
+/// Options that take a value; anything else starting with `--` is a flag.
+const VALUE_OPTIONS: [&str; 3] = ["loader", "mc", "version"];
+
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or("Falta el comando")?;
+        let mut parsed = Args {
+            command,
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if VALUE_OPTIONS.contains(&name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Falta el valor de --{}", name))?;
+                    parsed.options.insert(name.to_string(), value);
+                }
+                Some(name) => parsed.flags.push(name.to_string()),
+                None => parsed.positional.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<String> {
+        self.options.get(name).cloned()
+    }
+
+    fn required(&self, name: &str) -> Result<String, String> {
+        self.option(name)
+            .ok_or_else(|| format!("Falta la opción --{}", name))
+    }
+
+    fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Minecraft version, loader and loader version of an install command.
+    fn version(&self) -> Result<(String, ModLoaders, String), String> {
+        let loader =
+            launcher_core::parse_loader(&self.required("loader")?).map_err(|e| e.to_string())?;
+        let loader_version = match loader {
+            ModLoaders::Vanilla => self.option("version").unwrap_or_default(),
+            _ => self.required("version")?,
+        };
+        Ok((self.required("mc")?, loader, loader_version))
+    }
+}
+
+fn main() -> ExitCode {
+    match env::args().nth(1).as_deref() {
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some("--version" | "-V") => {
+            println!("canada-cli {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        _ => {}
+    }
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("no se pudo iniciar el runtime");
+    match runtime.block_on(run(args)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    let sink: Arc<dyn EventSink> = Arc::new(StderrSink);
+    events::set_default_sink(sink.clone());
+
+    match args.command.as_str() {
+        "sync" => {
+            launcher_core::sync_pack(sink)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        "install" => {
+            let (minecraft_version, loader, loader_version) = args.version()?;
+            launcher_core::install(sink, minecraft_version, loader, loader_version)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        "install-loader" => {
+            let (minecraft_version, loader, loader_version) = args.version()?;
+            launcher_core::install_loader(sink, minecraft_version, loader, loader_version)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        "create-profile" => {
+            let (minecraft_version, loader, loader_version) = args.version()?;
+            launcher_core::create_profile(sink, minecraft_version, loader, loader_version)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        "list-versions" => list_versions(&args, sink).await?,
+        "launch" => launcher_core::launch(sink).map_err(|e| e.to_string())?,
+        "publish" => publish(&args, sink).await?,
+        other => return Err(format!("Comando desconocido: {}\n\n{}", other, USAGE)),
+    }
+    Ok(())
+}
+
+async fn list_versions(args: &Args, sink: Arc<dyn EventSink>) -> Result<(), String> {
+    let versions = match args.positional.first().map(String::as_str) {
+        Some("minecraft") => {
+            let version_types = if args.has_flag("snapshots") {
+                vec![VersionType::Release, VersionType::Snapshot]
+            } else {
+                vec![VersionType::Release]
+            };
+            launcher_core::minecraft_manifest(sink, Some(version_types))
+                .await
+                .map_err(|e| e.to_string())?
+                .versions
+                .into_iter()
+                .map(|version| version.id)
+                .collect()
+        }
+        Some("fabric") => launcher_core::fabric_versions(sink)
+            .await
+            .map_err(|e| e.to_string())?,
+        Some("forge") => {
+            let minecraft_version = args.required("mc")?;
+            let forge = launcher_core::forge_versions(sink)
+                .await
+                .map_err(|e| e.to_string())?
+                .remove(&minecraft_version)
+                .ok_or_else(|| format!("No hay Forge para Minecraft {}", minecraft_version))?;
+            forge
+                .versions
+                .iter()
+                .map(|version| {
+                    if Some(version) == forge.recommended.as_ref() {
+                        format!("{} (recomendada)", version)
+                    } else if Some(version) == forge.latest.as_ref() {
+                        format!("{} (última)", version)
+                    } else {
+                        version.clone()
+                    }
+                })
+                .collect()
+        }
+        _ => return Err("Indica minecraft, fabric o forge".to_string()),
+    };
+    for version in versions {
+        println!("{}", version);
+    }
+    Ok(())
+}
+
+async fn publish(args: &Args, sink: Arc<dyn EventSink>) -> Result<(), String> {
+    let root = args
+        .positional
+        .first()
+        .ok_or("Indica la carpeta del pack")?;
+    let dry_run = args.has_flag("dry-run");
+    let [mods, resource_packs] = BucketConfig::baked();
+    dowloader::set_bucket_configs(
+        with_write_keys(mods, "MODS"),
+        with_write_keys(resource_packs, "RESP"),
+    );
+    let report = launcher_core::publish_pack(sink, Path::new(root), dry_run)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (uploaded, deleted) = if dry_run {
+        ("Se subirían", "Se eliminarían")
+    } else {
+        ("Subidos", "Eliminados")
+    };
+    println!("{}: {}", uploaded, report.uploaded.len());
+    for object in &report.uploaded {
+        println!("  + {}", object);
+    }
+    println!("{}: {}", deleted, report.deleted.len());
+    for object in &report.deleted {
+        println!("  - {}", object);
+    }
+    println!("Sin cambios: {}", report.unchanged);
+    Ok(())
+}
+
+/// Swaps the app's keys of a bucket for the `<prefix>_R2_*` write keys in
+/// the environment.
+fn with_write_keys(mut config: BucketConfig, prefix: &str) -> BucketConfig {
+    if let Ok(access_key) = env::var(format!("{}_R2_ACCESS_KEY", prefix)) {
+        config.access_key = access_key;
+    }
+    if let Ok(secret_key) = env::var(format!("{}_R2_SECRET_KEY", prefix)) {
+        config.secret_key = secret_key;
+    }
+    config
+}
@@ -0,0 +1,99 @@
+use std::{fs, future::Future, io::Error, path::PathBuf, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    api_error::ApiError, fs_util::write_atomic, log_to_frontend, settings::get_launcher_directory,
+};
+
+pub const MINECRAFT_MANIFEST_TTL: Duration = Duration::from_secs(60 * 60);
+pub const FABRIC_VERSIONS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+pub const FORGE_VERSIONS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<T> {
+    fetched_at: DateTime<Utc>,
+    data: T,
+}
+
+impl<T> CacheEntry<T> {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        Utc::now()
+            .signed_duration_since(self.fetched_at)
+            .to_std()
+            .is_ok_and(|age| age < ttl)
+    }
+}
+
+fn get_cache_path(name: &str) -> PathBuf {
+    get_launcher_directory()
+        .join("cache")
+        .join(format!("{}.json", name))
+}
+
+fn read_entry<T: DeserializeOwned>(name: &str) -> Option<CacheEntry<T>> {
+    let json = fs::read_to_string(get_cache_path(name)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Returns the cached `name` if it is younger than `ttl`, otherwise calls
+/// `fetch` and caches the result. When offline, or when `fetch` fails, the
+/// cached copy is used however old it is.
+pub async fn get_or_fetch<T, E, F, Fut>(
+    name: &str,
+    ttl: Duration,
+    offline: bool,
+    fetch: F,
+) -> Result<T, ApiError>
+where
+    T: Serialize + DeserializeOwned,
+    E: Into<ApiError>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let cached = read_entry::<T>(name);
+    match cached {
+        Some(entry) if offline || entry.is_fresh(ttl) => return Ok(entry.data),
+        None if offline => return Err(ApiError::Offline(name.to_string())),
+        _ => {}
+    }
+
+    match fetch().await {
+        Ok(data) => {
+            if let Err(e) = store(name, &data) {
+                eprintln!("No se pudo guardar la caché de {}: {}", name, e);
+            }
+            Ok(data)
+        }
+        Err(e) => match (e.into(), cached) {
+            (e, Some(entry)) => {
+                log_to_frontend(&format!(
+                    "Sin conexión ({}), usando los datos guardados de {} del {}",
+                    e,
+                    name,
+                    entry.fetched_at.format("%d/%m/%Y %H:%M")
+                ));
+                Ok(entry.data)
+            }
+            (e, None) => Err(e),
+        },
+    }
+}
+
+/// The cached `name`, however old.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    read_entry(name).map(|entry| entry.data)
+}
+
+pub fn store<T: Serialize>(name: &str, data: &T) -> Result<(), Error> {
+    let path = get_cache_path(name);
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let entry = CacheEntry {
+        fetched_at: Utc::now(),
+        data,
+    };
+    write_atomic(&path, serde_json::to_string(&entry)?.as_bytes())
+}
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{api_error::ApiError, endpoints::Endpoints, http};
 
-use reqwest::Error;
 #[derive(Serialize, Deserialize)]
 pub struct MinecraftProfile {
     pub name: String,
@@ -17,28 +19,140 @@ pub struct MinecraftProfile {
     pub icon: String,
     #[serde(rename = "lastUsed")]
     pub last_used: String,
+    #[serde(rename = "type")]
+    pub profile_type: String,
+    pub created: String,
+    #[serde(rename = "javaDir", skip_serializing_if = "Option::is_none")]
+    pub java_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<ProfileResolution>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ProfileResolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Which Java the launcher profile points at.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "path")]
+pub enum JavaSelection {
+    /// Let the Minecraft Launcher pick its bundled Java.
+    #[default]
+    Launcher,
+    /// The Mojang runtime downloaded by Canada Launcher.
+    Managed,
+    Custom(String),
+}
+
+/// Optional `pack.json` at the root of the mods bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PackManifest {
+    /// PNG icon for the launcher profile, as base64 or a `data:` URI.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Servers added to the instance's server list.
+    #[serde(default)]
+    pub servers: Vec<PackServer>,
+    #[serde(default)]
+    pub options: PackOptions,
+    /// Resource packs to enable, top priority first. When missing every
+    /// synced pack is enabled in name order.
+    #[serde(default)]
+    pub resource_packs: Option<Vec<String>>,
+    /// Every published file, written by `canada-cli publish`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PackFile>,
+}
+
+/// A file of the pack, by its path in the published tree, e.g.
+/// `mods/sodium.jar` or `config/sodium-options.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackFile {
+    pub path: String,
+    pub sha1: String,
+    pub size: u64,
+}
+
+/// Default `options.txt` keys. They are written on the first install only,
+/// except the `enforced` ones, which are written on every install.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PackOptions {
+    #[serde(default)]
+    pub defaults: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub enforced: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackServer {
+    pub name: String,
+    pub ip: String,
+    /// 64x64 PNG shown in the server list, as base64 or a `data:` URI.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinecraftApiResponse {
-    latest: MinecraftLatest,
+    pub latest: MinecraftLatest,
     pub versions: Vec<MinecraftVersion>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinecraftLatest {
-    release: String,
-    snapshot: String,
+    pub release: String,
+    pub snapshot: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MinecraftVersion {
     pub id: String,
     #[serde(rename = "type")]
-    pub version_type: String,
-    url: String,
-    time: String,
-    release_time: Option<String>,
+    pub version_type: VersionType,
+    pub url: String,
+    pub time: DateTime<Utc>,
+    #[serde(rename = "releaseTime")]
+    pub release_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+    Release,
+    Snapshot,
+    OldBeta,
+    OldAlpha,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MinecraftVersionDetails {
+    pub id: String,
+    pub downloads: MinecraftVersionDownloads,
+    #[serde(rename = "javaVersion")]
+    pub java_version: Option<JavaVersion>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JavaVersion {
+    pub component: String,
+    #[serde(rename = "majorVersion")]
+    pub major_version: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MinecraftVersionDownloads {
+    pub client: MinecraftDownload,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MinecraftDownload {
+    pub url: String,
+    pub sha1: String,
+    pub size: u64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -54,6 +168,7 @@ pub enum ModLoaders {
     Forge,
     #[default]
     Fabric,
+    Vanilla,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,17 +177,20 @@ pub struct ForgeApiResponse {
     pub promos: HashMap<String, String>,
 }
 
-pub async fn get_fabric_versions() -> Result<Vec<String>, Error> {
-    let api = "https://meta2.fabricmc.net/v2/versions/installer";
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ForgeVersions {
+    pub versions: Vec<String>,
+    pub recommended: Option<String>,
+    pub latest: Option<String>,
+}
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(api)
-        .header("User-Agent", "reqwest")
-        .send()
-        .await?;
+async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, ApiError> {
+    Ok(http::get_json(url).await?)
+}
 
-    let api_response: Vec<FabricApiResponse> = response.json().await?;
+pub async fn get_fabric_versions() -> Result<Vec<String>, ApiError> {
+    let api = Endpoints::current().fabric_installers();
+    let api_response: Vec<FabricApiResponse> = get_json(&api).await?;
 
     let versions: Vec<String> = api_response
         .into_iter()
@@ -81,22 +199,57 @@ pub async fn get_fabric_versions() -> Result<Vec<String>, Error> {
     Ok(versions)
 }
 
-pub async fn get_minecraft_versions() -> Result<Vec<String>, reqwest::Error> {
-    let api = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
-    let response = reqwest::get(api).await?;
-    let api_response: MinecraftApiResponse = response.json().await?;
-    let release_versions: Vec<String> = api_response
-        .versions
-        .into_iter()
-        .filter(|version| version.version_type == "release")
-        .map(|version| version.id)
-        .collect();
-    Ok(release_versions)
+pub async fn get_minecraft_manifest() -> Result<MinecraftApiResponse, ApiError> {
+    get_json(&Endpoints::current().minecraft_manifest()).await
 }
 
-pub async fn get_forge_versions() -> Result<HashMap<String, String>, reqwest::Error> {
-    let api = "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
-    let response = reqwest::get(api).await?;
-    let api_response: ForgeApiResponse = response.json().await?;
+/// Keeps only the requested version types, or every version when no filter
+/// is given. Versions stay sorted newest first.
+pub fn filter_minecraft_manifest(
+    mut manifest: MinecraftApiResponse,
+    version_types: Option<&[VersionType]>,
+) -> MinecraftApiResponse {
+    if let Some(version_types) = version_types {
+        manifest
+            .versions
+            .retain(|version| version_types.contains(&version.version_type));
+    }
+    manifest
+}
+
+pub async fn get_forge_promotions() -> Result<HashMap<String, String>, ApiError> {
+    let api = Endpoints::current().forge_promotions();
+    let api_response: ForgeApiResponse = get_json(&api).await?;
     Ok(api_response.promos)
 }
+
+/// Every Forge build grouped by Minecraft version, newest build first.
+/// Builds are returned without the `<minecraft>-` prefix so they can be fed
+/// straight into the installer URL.
+pub async fn get_forge_versions() -> Result<HashMap<String, ForgeVersions>, ApiError> {
+    let api = Endpoints::current().forge_metadata();
+    let metadata: HashMap<String, Vec<String>> = get_json(&api).await?;
+    let promos = get_forge_promotions().await?;
+
+    let versions = metadata
+        .into_iter()
+        .map(|(minecraft_version, builds)| {
+            let prefix = format!("{}-", minecraft_version);
+            let forge_versions = ForgeVersions {
+                versions: builds
+                    .iter()
+                    .rev()
+                    .map(|build| build.strip_prefix(&prefix).unwrap_or(build).to_string())
+                    .collect(),
+                recommended: promos
+                    .get(&format!("{}-recommended", minecraft_version))
+                    .cloned(),
+                latest: promos
+                    .get(&format!("{}-latest", minecraft_version))
+                    .cloned(),
+            };
+            (minecraft_version, forge_versions)
+        })
+        .collect();
+    Ok(versions)
+}
@@ -1,20 +1,34 @@
-use crate::{data_structs::ModLoaders, log_to_frontend};
+use crate::{
+    cache,
+    data_structs::{JavaSelection, ModLoaders, PackManifest, ProfileResolution},
+    log_to_frontend,
+};
 use aws_sdk_s3::{
-    config::{Credentials, Region},
+    config::{Credentials, Region, RequestChecksumCalculation},
     Client, Config,
 };
 use dirs::config_dir;
 use dotenv::dotenv;
 use dotenv_codegen::dotenv;
+mod forge_installer;
+mod game_options;
+mod install_error;
+mod installer_process;
+mod java_runtime;
+mod launcher_profiles;
 mod loader_installer;
-use loader_installer::LoaderInstaller;
+mod pack_publisher;
+mod servers_dat;
+pub use install_error::InstallError;
+pub use loader_installer::LoaderInstaller;
 use minecraft_instancier::MinecraftInstancier;
+pub use pack_publisher::{publish_pack, PublishReport};
 mod minecraft_instancier;
 use std::{
-    error::Error,
     fs::{self, File},
     io::Write,
     path::PathBuf,
+    sync::OnceLock,
     time::SystemTime,
 };
 
@@ -25,105 +39,339 @@ fn get_env_var(key: &str, value: &str) -> String {
     value.to_string()
 }
 
-pub async fn start_install(loader: ModLoaders, loader_version: String, minecraft_version: String) {
-    dowload_mods().await;
-    let loader_installer = LoaderInstaller {
+/// Where one of the buckets lives and the keys to reach it.
+#[derive(Clone, Debug)]
+pub struct BucketConfig {
+    pub access_key: String,
+    pub secret_key: String,
+    pub endpoint: String,
+    pub bucket: String,
+    /// Addresses the bucket in the path instead of the host name, for local
+    /// S3 servers.
+    pub force_path_style: bool,
+}
+
+impl BucketConfig {
+    /// The mods and resource packs buckets baked in from `.env` at build
+    /// time.
+    pub fn baked() -> [BucketConfig; 2] {
+        [
+            BucketConfig {
+                access_key: get_env_var("MODS_R2_ACCESS_KEY", dotenv!("MODS_R2_ACCESS_KEY")),
+                secret_key: get_env_var("MODS_R2_SECRET_KEY", dotenv!("MODS_R2_SECRET_KEY")),
+                endpoint: get_env_var("MODS_R2_ENDPOINT", dotenv!("MODS_R2_ENDPOINT")),
+                bucket: get_env_var("MODS_R2_BUCKET", dotenv!("MODS_R2_BUCKET")),
+                force_path_style: false,
+            },
+            BucketConfig {
+                access_key: get_env_var("RESP_R2_ACCESS_KEY", dotenv!("RESP_R2_ACCESS_KEY")),
+                secret_key: get_env_var("RESP_R2_SECRET_KEY", dotenv!("RESP_R2_SECRET_KEY")),
+                endpoint: get_env_var("RESP_R2_ENDPOINT", dotenv!("RESP_R2_ENDPOINT")),
+                bucket: get_env_var("RESP_R2_BUCKET", dotenv!("RESP_R2_BUCKET")),
+                force_path_style: false,
+            },
+        ]
+    }
+}
+
+static BUCKET_CONFIGS: OnceLock<[BucketConfig; 2]> = OnceLock::new();
+
+/// Replaces the baked-in buckets for the rest of the process, e.g. to publish
+/// with write keys or to test against a local server. Only the first call
+/// has an effect, and only before the buckets are first used.
+pub fn set_bucket_configs(mods: BucketConfig, resource_packs: BucketConfig) {
+    let _ = BUCKET_CONFIGS.set([mods, resource_packs]);
+}
+
+pub const DEFAULT_INSTANCE_ID: &str = "default";
+const PACK_MANIFEST_KEY: &str = "pack.json";
+/// Keys of the mods bucket holding the pack's configs, written by the
+/// publisher.
+const CONFIG_PREFIX: &str = "config/";
+
+const PACK_MANIFEST_CACHE: &str = "pack_manifest";
+
+/// Per-instance values for one install, mostly written into the launcher
+/// profile.
+#[derive(Clone, Debug, Default)]
+pub struct InstanceOptions {
+    /// `javaArgs` from the saved memory settings; `None` keeps the profile's.
+    pub java_args: Option<String>,
+    pub java: JavaSelection,
+    pub resolution: Option<ProfileResolution>,
+    /// Launch what is installed without syncing the pack or the loader.
+    pub offline: bool,
+}
+
+pub async fn start_install(
+    instance_id: &str,
+    loader: ModLoaders,
+    loader_version: String,
+    minecraft_version: String,
+    options: InstanceOptions,
+) -> Result<(), InstallError> {
+    let pack_manifest = if options.offline {
+        log_to_frontend("Modo sin conexión: se omite la sincronización del pack.");
+        get_cached_pack_manifest()
+    } else {
+        sync_pack(instance_id).await?
+    };
+    let loader_installer = get_loader_installer(loader, loader_version, minecraft_version);
+
+    if options.offline && !loader_installer.is_version_installed() {
+        return Err(InstallError::Network(format!(
+            "Modo sin conexión: {} no está instalado, conéctate para instalarlo",
+            loader_installer.get_version_format()
+        )));
+    }
+    loader_installer.install_loader().await?;
+
+    create_profile(instance_id, &loader_installer, options, pack_manifest).await
+}
+
+pub fn get_loader_installer(
+    loader: ModLoaders,
+    loader_version: String,
+    minecraft_version: String,
+) -> LoaderInstaller {
+    LoaderInstaller {
         loader,
-        loader_version: loader_version.clone(),
+        loader_version,
         minecraft_version,
         minecraft_directory: get_minecraft_directory(),
-    };
+        runtime_directory: get_minecraft_canada_directory().join("runtime"),
+        logs_directory: get_minecraft_canada_directory().join("logs"),
+    }
+}
 
-    let mut launcher_profiles_directory = get_minecraft_directory();
-    launcher_profiles_directory.push("launcher_profiles.json");
+/// Syncs the buckets into `instance_id` and keeps the pack manifest for
+/// offline installs.
+pub async fn sync_pack(instance_id: &str) -> Result<PackManifest, InstallError> {
+    let pack_manifest = dowload_mods(instance_id).await?;
+    if let Err(e) = cache::store(PACK_MANIFEST_CACHE, &pack_manifest) {
+        log_to_frontend(&format!("No se pudo guardar el manifiesto del pack: {}", e));
+    }
+    Ok(pack_manifest)
+}
+
+/// Pack manifest saved by the last sync, or the default one.
+pub fn get_cached_pack_manifest() -> PackManifest {
+    cache::load(PACK_MANIFEST_CACHE).unwrap_or_default()
+}
+
+/// Writes the launcher profile of `instance_id` for the version installed by
+/// `loader_installer`, then applies the pack's options, resource packs and
+/// servers.
+pub async fn create_profile(
+    instance_id: &str,
+    loader_installer: &LoaderInstaller,
+    options: InstanceOptions,
+    pack_manifest: PackManifest,
+) -> Result<(), InstallError> {
+    let java_dir = match options.java {
+        JavaSelection::Launcher => None,
+        JavaSelection::Managed => Some(loader_installer.get_managed_java(options.offline).await?),
+        JavaSelection::Custom(path) => Some(PathBuf::from(path)),
+    };
 
     let minecraft_instancier = MinecraftInstancier {
-        minecraft_canada_directory: get_minecraft_canada_directory(),
-        launcher_profiles_directory,
+        instance_id: instance_id.to_string(),
+        minecraft_canada_directory: get_instance_directory(instance_id),
+        launcher_profiles_directory: get_launcher_profiles_directory(),
         loader_version: loader_installer.get_version_format(),
+        java_args: options.java_args,
+        java_dir,
+        resolution: options.resolution,
+        icon: pack_manifest.icon,
+        options: pack_manifest.options,
+        resource_packs: pack_manifest.resource_packs,
     };
 
-    loader_installer.install_loader().await;
-    match minecraft_instancier.create_minecraft_instance() {
-        Err(e) => {
+    minecraft_instancier
+        .create_minecraft_instance()
+        .map_err(|e| {
             log_to_frontend(&format!("Error creating minecraft instance files: {}", e));
-        }
-        Ok(()) => (),
-    };
+            InstallError::ProfileWrite(e)
+        })?;
+
+    if let Err(e) = minecraft_instancier.apply_game_options() {
+        log_to_frontend(&format!(
+            "No se pudieron aplicar las opciones del pack: {}",
+            e
+        ));
+    }
+    if let Err(e) = minecraft_instancier.enable_resource_packs() {
+        log_to_frontend(&format!(
+            "No se pudieron activar los paquetes de recursos: {}",
+            e
+        ));
+    }
+
+    // A broken server list should not keep the player from launching.
+    if let Err(e) = servers_dat::update_servers(
+        &get_instance_directory(instance_id).join("servers.dat"),
+        &pack_manifest.servers,
+    ) {
+        log_to_frontend(&format!(
+            "No se pudo actualizar la lista de servidores: {}",
+            e
+        ));
+    }
+    Ok(())
+}
+
+pub fn remove_instance_profile(instance_id: &str) -> Result<bool, InstallError> {
+    minecraft_instancier::remove_minecraft_profile(&get_launcher_profiles_directory(), instance_id)
+        .map_err(InstallError::ProfileWrite)
+}
+
+/// Backups of launcher_profiles.json, newest first.
+pub fn list_profile_backups() -> Result<Vec<String>, InstallError> {
+    let backups = launcher_profiles::list_backups(&get_launcher_profiles_directory())?;
+    Ok(backups
+        .iter()
+        .filter_map(|backup| backup.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect())
 }
 
-fn get_clients() -> (Vec<Client>, Vec<String>, Vec<PathBuf>) {
+/// Restores the named backup of launcher_profiles.json, or the newest one.
+pub fn restore_profile_backup(backup_name: Option<&str>) -> Result<String, InstallError> {
+    launcher_profiles::restore_backup(&get_launcher_profiles_directory(), backup_name)
+        .map(|backup| backup.to_string_lossy().to_string())
+        .map_err(InstallError::ProfileWrite)
+}
+
+fn get_launcher_profiles_directory() -> PathBuf {
+    let mut launcher_profiles_directory = get_minecraft_directory();
+    launcher_profiles_directory.push("launcher_profiles.json");
+    launcher_profiles_directory
+}
+
+fn get_clients() -> (Vec<Client>, Vec<String>) {
     let mut clients = Vec::<Client>::new();
     let mut buckets = Vec::<String>::new();
-    let mut directories = Vec::<PathBuf>::new();
 
-    let mut access_key;
-    let mut secret_key;
-    let mut endpoint_url;
-    let mut bucket_name;
     let region = Region::new("us-east-1");
-    for i in 0..2 {
-        match i {
-            0 => {
-                access_key = get_env_var("MODS_R2_ACCESS_KEY", dotenv!("MODS_R2_ACCESS_KEY"));
-                secret_key = get_env_var("MODS_R2_SECRET_KEY", dotenv!("MODS_R2_SECRET_KEY"));
-                endpoint_url = get_env_var("MODS_R2_ENDPOINT", dotenv!("MODS_R2_ENDPOINT"));
-                bucket_name = get_env_var("MODS_R2_BUCKET", dotenv!("MODS_R2_BUCKET"));
-                buckets.push(bucket_name);
-                let mut dir = get_minecraft_canada_directory();
-                dir.push("mods");
-                directories.push(dir);
-            }
-            1 => {
-                access_key = get_env_var("RESP_R2_ACCESS_KEY", dotenv!("RESP_R2_ACCESS_KEY"));
-                secret_key = get_env_var("RESP_R2_SECRET_KEY", dotenv!("RESP_R2_SECRET_KEY"));
-                endpoint_url = get_env_var("RESP_R2_ENDPOINT", dotenv!("RESP_R2_ENDPOINT"));
-                bucket_name = get_env_var("RESP_R2_BUCKET", dotenv!("RESP_R2_BUCKET"));
-                buckets.push(bucket_name);
-                let mut dir = get_minecraft_canada_directory();
-                dir.push("resourcepacks");
-                directories.push(dir);
-            }
-            _ => panic!("Error in fetching clients"),
-        }
-        let credentials = Credentials::new(&access_key, &secret_key, None, None, "loaded-from-env");
+    let configs = BUCKET_CONFIGS.get_or_init(BucketConfig::baked);
+    for bucket in configs {
+        buckets.push(bucket.bucket.clone());
+
+        let credentials = Credentials::new(
+            &bucket.access_key,
+            &bucket.secret_key,
+            None,
+            None,
+            "loaded-from-env",
+        );
 
         let config = Config::builder()
             .credentials_provider(credentials)
             .region(region.clone())
-            .endpoint_url(endpoint_url.clone())
+            .endpoint_url(bucket.endpoint.clone())
+            .force_path_style(bucket.force_path_style)
+            // R2 has not always accepted the chunked, checksummed uploads the
+            // SDK sends by default.
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
             .behavior_version_latest()
             .build();
 
         clients.push(Client::from_conf(config));
     }
-    (clients, buckets, directories)
+    (clients, buckets)
 }
 
-async fn dowload_mods() {
+/// Syncs the mods and resource packs buckets into the game directory of
+/// `instance_id`.
+pub async fn dowload_mods(instance_id: &str) -> Result<PackManifest, InstallError> {
     dotenv().ok();
-    let (clients, bucket_names, directories) = get_clients();
-    for ((client, bucket_name), directory) in clients
+    let (clients, bucket_names) = get_clients();
+    let instance_directory = get_instance_directory(instance_id);
+    let mut pack_manifest = PackManifest::default();
+    for (index, ((client, bucket_name), folder)) in clients
         .into_iter()
-        .zip(bucket_names.into_iter())
-        .zip(directories.into_iter())
+        .zip(bucket_names)
+        .zip(["mods", "resourcepacks"])
+        .enumerate()
     {
-        match sync_files(&client, &bucket_name, directory).await {
-            Err(e) => {
+        let directory = instance_directory.join(folder);
+        // The mods bucket also holds the configs uploaded by the publisher,
+        // which are not mods and would not fit in the flat mods folder.
+        let skipped_prefix = (index == 0).then_some(CONFIG_PREFIX);
+        let synced = sync_files(&client, &bucket_name, directory, skipped_prefix)
+            .await
+            .inspect_err(|e| {
                 log_to_frontend(&format!("Error to sync files: {}", e));
-            }
-            Ok(()) => (),
-        };
+            });
+        // Without the mods the game would not match the server, but it still
+        // runs with outdated resource packs.
+        if index == 0 {
+            synced?;
+        } else if synced.is_err() {
+            log_to_frontend("Se continúa con los paquetes de recursos que ya había.");
+        }
+        // The pack manifest lives in the mods bucket, the first client.
+        if index == 0 {
+            pack_manifest = get_pack_manifest(&client, &bucket_name).await?;
+        }
     }
+    Ok(pack_manifest)
+}
+
+async fn get_pack_manifest(client: &Client, bucket: &str) -> Result<PackManifest, InstallError> {
+    let resp = match client
+        .get_object()
+        .bucket(bucket)
+        .key(PACK_MANIFEST_KEY)
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+            return Ok(PackManifest::default());
+        }
+        Err(e) => return Err(InstallError::Network(e.to_string())),
+    };
+    let body_bytes = resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| InstallError::Network(e.to_string()))?
+        .into_bytes();
+    // The manifest only adds extras to the synced files, so a broken one
+    // should not stop the install.
+    Ok(serde_json::from_slice(&body_bytes).unwrap_or_else(|e| {
+        log_to_frontend(&format!(
+            "El {} del pack no es válido, se ignora: {}",
+            PACK_MANIFEST_KEY, e
+        ));
+        PackManifest::default()
+    }))
 }
 
-async fn sync_files(
+/// Mirrors `bucket` into `directory`, leaving out the keys starting with
+/// `skipped_prefix`.
+pub async fn sync_files(
     client: &Client,
     bucket: &str,
     directory: PathBuf,
-) -> Result<(), Box<dyn Error>> {
-    let resp = client.list_objects_v2().bucket(bucket).send().await?;
-    let remote_files = resp.contents();
+    skipped_prefix: Option<&str>,
+) -> Result<(), InstallError> {
+    let resp = client
+        .list_objects_v2()
+        .bucket(bucket)
+        .send()
+        .await
+        .map_err(|e| InstallError::Network(e.to_string()))?;
+    // The pack manifest is read separately and never copied into the instance.
+    let remote_files: Vec<_> = resp
+        .contents()
+        .iter()
+        .filter(|obj| obj.key() != Some(PACK_MANIFEST_KEY))
+        .filter(|obj| {
+            skipped_prefix.is_none_or(|prefix| !obj.key().unwrap_or_default().starts_with(prefix))
+        })
+        .collect();
 
     if remote_files.is_empty() {
         log_to_frontend("El bucket está vacío o la lista está restringida.");
@@ -133,10 +381,10 @@ async fn sync_files(
         .iter()
         .filter_map(|obj| obj.key().map(String::from))
         .collect();
-    if !fs::exists(&directory).unwrap() {
-        let _ = fs::create_dir(&directory);
+    if !fs::exists(&directory)? {
+        fs::create_dir_all(&directory)?;
     }
-    for obj in remote_files {
+    for obj in &remote_files {
         if let Some(key) = obj.key() {
             let remote_size = obj.size;
             let remote_modified = obj
@@ -148,13 +396,13 @@ async fn sync_files(
             path.push(key);
             let local_file_path = path.to_str().unwrap_or_default();
 
-            if should_download(&local_file_path, remote_size.unwrap(), remote_modified) {
+            if should_download(local_file_path, remote_size.unwrap(), remote_modified) {
                 log_to_frontend(&format!(
                     "\nDescargando archivo actualizado: {} en {}",
                     key, local_file_path
                 ));
 
-                download_file(client, bucket, key, &local_file_path).await?;
+                download_file(client, bucket, key, local_file_path).await?;
             } else {
                 log_to_frontend(&format!(
                     "No se detectaron cambios para '{}', omitiendo...",
@@ -185,9 +433,9 @@ fn should_download(local_path: &str, remote_size: i64, remote_modified: f64) ->
 }
 
 fn delete_missing_local_files(
-    remote_files: &Vec<String>,
+    remote_files: &[String],
     directory: PathBuf,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), InstallError> {
     let local_files: Vec<String> = fs::read_dir(&directory)?
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| entry.file_name().into_string().ok())
@@ -213,9 +461,20 @@ async fn download_file(
     bucket: &str,
     key: &str,
     save_path: &str,
-) -> Result<(), Box<dyn Error>> {
-    let resp = client.get_object().bucket(bucket).key(key).send().await?;
-    let body_bytes = resp.body.collect().await?.into_bytes();
+) -> Result<(), InstallError> {
+    let resp = client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| InstallError::Network(e.to_string()))?;
+    let body_bytes = resp
+        .body
+        .collect()
+        .await
+        .map_err(|e| InstallError::Network(e.to_string()))?
+        .into_bytes();
 
     log_to_frontend(&format!(
         "Descargando '{}', tamaño: {} bytes",
@@ -230,7 +489,7 @@ async fn download_file(
     Ok(())
 }
 
-fn get_minecraft_canada_directory() -> PathBuf {
+pub fn get_minecraft_canada_directory() -> PathBuf {
     if let Some(mut appdata) = config_dir() {
         appdata.push(".minecraftCanada");
         return appdata;
@@ -239,7 +498,25 @@ fn get_minecraft_canada_directory() -> PathBuf {
     PathBuf::new()
 }
 
-fn get_minecraft_directory() -> PathBuf {
+/// Game directory of `instance_id`. The default instance keeps
+/// `.minecraftCanada`, where every install lived before instances; the others
+/// get `.minecraftCanada-<id>` next to it.
+pub fn get_instance_directory(instance_id: &str) -> PathBuf {
+    let directory = get_minecraft_canada_directory();
+    if instance_id == DEFAULT_INSTANCE_ID {
+        return directory;
+    }
+    let name: String = instance_id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    directory.with_file_name(format!(".minecraftCanada-{}", name))
+}
+
+pub fn get_minecraft_directory() -> PathBuf {
     if let Some(mut appdata) = config_dir() {
         appdata.push(".minecraft");
         return appdata;
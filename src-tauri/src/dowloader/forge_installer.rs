@@ -0,0 +1,411 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use zip::ZipArchive;
+
+use super::{install_error::InstallError, installer_process::InstallLog, java_runtime::file_sha1};
+use crate::{endpoints::Endpoints, fs_util::create_temp_directory, http, log_to_frontend};
+
+#[derive(Deserialize)]
+struct InstallProfile {
+    json: Option<String>,
+    #[serde(default)]
+    data: HashMap<String, DataEntry>,
+    #[serde(default)]
+    processors: Vec<Processor>,
+    #[serde(default)]
+    libraries: Vec<Library>,
+    install: Option<LegacyInstall>,
+    #[serde(rename = "versionInfo")]
+    version_info: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct DataEntry {
+    client: String,
+}
+
+#[derive(Deserialize)]
+struct Processor {
+    sides: Option<Vec<String>>,
+    jar: String,
+    #[serde(default)]
+    classpath: Vec<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    outputs: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Library {
+    name: String,
+    url: Option<String>,
+    downloads: Option<LibraryDownloads>,
+}
+
+#[derive(Deserialize)]
+struct LibraryDownloads {
+    artifact: Option<Artifact>,
+}
+
+#[derive(Deserialize)]
+struct Artifact {
+    path: String,
+    url: String,
+    sha1: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionJson {
+    id: String,
+    #[serde(default)]
+    libraries: Vec<Library>,
+}
+
+#[derive(Deserialize)]
+struct LegacyInstall {
+    path: String,
+    #[serde(rename = "filePath")]
+    file_path: String,
+    target: String,
+}
+
+/// Installs the Forge client from `installer_path` without running the
+/// installer's own UI: libraries are fetched here and the processors are run
+/// with `java`, their output going to `install_log`.
+pub async fn install(
+    installer_path: &Path,
+    minecraft_directory: &Path,
+    minecraft_version: &str,
+    java: &Path,
+    install_log: &mut InstallLog,
+) -> Result<(), InstallError> {
+    let mut installer = ZipArchive::new(File::open(installer_path)?)?;
+    let profile: InstallProfile =
+        serde_json::from_str(&read_entry(&mut installer, "install_profile.json")?)?;
+    let libraries_directory = minecraft_directory.join("libraries");
+
+    if let (Some(install), Some(version_info)) = (&profile.install, &profile.version_info) {
+        log_to_frontend("Instalador de Forge antiguo detectado.");
+        let universal = libraries_directory.join(get_maven_path(&install.path)?);
+        extract_entry(&mut installer, &install.file_path, &universal)?;
+        write_version_json(
+            minecraft_directory,
+            &install.target,
+            &serde_json::to_string_pretty(version_info)?,
+        )?;
+        return Ok(());
+    }
+
+    let version_entry = profile
+        .json
+        .as_deref()
+        .unwrap_or("/version.json")
+        .trim_start_matches('/')
+        .to_string();
+    let version_contents = read_entry(&mut installer, &version_entry)?;
+    let version: VersionJson = serde_json::from_str(&version_contents)?;
+
+    log_to_frontend(&format!(
+        "Descargando librerías de Forge ({})...",
+        profile.libraries.len() + version.libraries.len()
+    ));
+    for library in profile.libraries.iter().chain(version.libraries.iter()) {
+        install_library(library, &libraries_directory, &mut installer).await?;
+    }
+
+    // Each install gets its own work directory, so two launchers or the CLI
+    // next to the app never overwrite each other's processor files.
+    let work_directory = create_temp_directory("canada_forge_installer")?;
+    let result: Result<(), InstallError> = async {
+        let mut data = HashMap::new();
+        for (key, entry) in &profile.data {
+            let value = resolve_data(
+                &entry.client,
+                &libraries_directory,
+                &work_directory,
+                &mut installer,
+            )?;
+            data.insert(key.clone(), value);
+        }
+        let minecraft_jar = minecraft_directory
+            .join("versions")
+            .join(minecraft_version)
+            .join(format!("{}.jar", minecraft_version));
+        data.insert("SIDE".to_string(), "client".to_string());
+        data.insert(
+            "MINECRAFT_VERSION".to_string(),
+            minecraft_version.to_string(),
+        );
+        data.insert("MINECRAFT_JAR".to_string(), path_string(&minecraft_jar));
+        data.insert("ROOT".to_string(), path_string(minecraft_directory));
+        data.insert("INSTALLER".to_string(), path_string(installer_path));
+        data.insert("LIBRARY_DIR".to_string(), path_string(&libraries_directory));
+
+        for processor in &profile.processors {
+            if let Some(sides) = &processor.sides {
+                if !sides.iter().any(|side| side == "client") {
+                    continue;
+                }
+            }
+            run_processor(processor, &data, &libraries_directory, java, install_log).await?;
+        }
+
+        write_version_json(minecraft_directory, &version.id, &version_contents)?;
+        Ok(())
+    }
+    .await;
+    let _ = fs::remove_dir_all(&work_directory);
+    result
+}
+
+async fn run_processor(
+    processor: &Processor,
+    data: &HashMap<String, String>,
+    libraries_directory: &Path,
+    java: &Path,
+    install_log: &mut InstallLog,
+) -> Result<(), InstallError> {
+    let outputs = processor
+        .outputs
+        .iter()
+        .map(|(file, sha1)| {
+            Ok((
+                replace_tokens(file, data, libraries_directory)?,
+                replace_tokens(sha1, data, libraries_directory)?,
+            ))
+        })
+        .collect::<Result<Vec<(String, String)>, InstallError>>()?;
+    if !outputs.is_empty() && outputs_match(&outputs) {
+        log_to_frontend(&format!(
+            "Procesador {} ya aplicado, omitiendo.",
+            processor.jar
+        ));
+        return Ok(());
+    }
+
+    let jar = libraries_directory.join(get_maven_path(&processor.jar)?);
+    let main_class = get_main_class(&jar)?;
+    let separator = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+    let classpath = std::iter::once(Ok(jar))
+        .chain(
+            processor
+                .classpath
+                .iter()
+                .map(|library| get_maven_path(library).map(|path| libraries_directory.join(path))),
+        )
+        .map(|path| path.map(|path| path_string(&path)))
+        .collect::<Result<Vec<String>, InstallError>>()?
+        .join(separator);
+    let args = processor
+        .args
+        .iter()
+        .map(|arg| replace_tokens(arg, data, libraries_directory))
+        .collect::<Result<Vec<String>, InstallError>>()?;
+
+    log_to_frontend(&format!(
+        "Ejecutando procesador de Forge: {}",
+        processor.jar
+    ));
+    let mut command = Command::new(java);
+    command.arg("-cp").arg(classpath).arg(main_class).args(args);
+    install_log
+        .run(command, &format!("El procesador {}", processor.jar))
+        .await?;
+
+    for (file, sha1) in &outputs {
+        if file_sha1(Path::new(file))? != *sha1 {
+            return Err(InstallError::InvalidData(format!(
+                "El archivo generado {} no es válido",
+                file
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn outputs_match(outputs: &[(String, String)]) -> bool {
+    outputs.iter().all(|(file, sha1)| {
+        file_sha1(Path::new(file))
+            .map(|actual| actual == *sha1)
+            .unwrap_or(false)
+    })
+}
+
+async fn install_library(
+    library: &Library,
+    libraries_directory: &Path,
+    installer: &mut ZipArchive<File>,
+) -> Result<(), InstallError> {
+    let (path, url, sha1) = match library.downloads.as_ref().and_then(|d| d.artifact.as_ref()) {
+        Some(artifact) => (
+            PathBuf::from(&artifact.path),
+            artifact.url.clone(),
+            artifact.sha1.clone(),
+        ),
+        None => {
+            let path = get_maven_path(&library.name)?;
+            let base = library
+                .url
+                .clone()
+                .unwrap_or_else(|| Endpoints::current().libraries());
+            let url = format!("{}{}", base, path.to_string_lossy().replace('\\', "/"));
+            (path, url, None)
+        }
+    };
+    let target = libraries_directory.join(&path);
+    if target.exists() {
+        match &sha1 {
+            Some(sha1) if file_sha1(&target)? != *sha1 => {}
+            _ => return Ok(()),
+        }
+    }
+
+    if url.is_empty() {
+        let entry = format!("maven/{}", path.to_string_lossy().replace('\\', "/"));
+        return extract_entry(installer, &entry, &target);
+    }
+
+    log_to_frontend(&format!("Descargando librería: {}", library.name));
+    let bytes = http::get_bytes(&url).await?;
+    if let Some(sha1) = &sha1 {
+        if format!("{:x}", Sha1::digest(&bytes)) != *sha1 {
+            return Err(InstallError::InvalidData(format!(
+                "La librería {} está corrupta",
+                library.name
+            )));
+        }
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, &bytes)?;
+    Ok(())
+}
+
+fn resolve_data(
+    value: &str,
+    libraries_directory: &Path,
+    work_directory: &Path,
+    installer: &mut ZipArchive<File>,
+) -> Result<String, InstallError> {
+    if let Some(coordinates) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Ok(path_string(
+            &libraries_directory.join(get_maven_path(coordinates)?),
+        ));
+    }
+    if let Some(literal) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return Ok(literal.to_string());
+    }
+    if let Some(entry) = value.strip_prefix('/') {
+        let target = work_directory.join(entry);
+        extract_entry(installer, entry, &target)?;
+        return Ok(path_string(&target));
+    }
+    Ok(value.to_string())
+}
+
+fn replace_tokens(
+    value: &str,
+    data: &HashMap<String, String>,
+    libraries_directory: &Path,
+) -> Result<String, InstallError> {
+    if let Some(coordinates) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Ok(path_string(
+            &libraries_directory.join(get_maven_path(coordinates)?),
+        ));
+    }
+    if let Some(literal) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return Ok(literal.to_string());
+    }
+    let mut replaced = value.to_string();
+    for (key, data_value) in data {
+        replaced = replaced.replace(&format!("{{{}}}", key), data_value);
+    }
+    Ok(replaced)
+}
+
+/// Turns `group:artifact:version[:classifier][@extension]` into the relative
+/// path the library has inside a maven repository.
+fn get_maven_path(coordinates: &str) -> Result<PathBuf, InstallError> {
+    let (coordinates, extension) = coordinates.split_once('@').unwrap_or((coordinates, "jar"));
+    let parts: Vec<&str> = coordinates.split(':').collect();
+    if parts.len() < 3 {
+        return Err(InstallError::InvalidData(format!(
+            "Coordenadas de librería inválidas: {}",
+            coordinates
+        )));
+    }
+    let (group, artifact, version) = (parts[0], parts[1], parts[2]);
+    let file_name = match parts.get(3) {
+        Some(classifier) => format!("{}-{}-{}.{}", artifact, version, classifier, extension),
+        None => format!("{}-{}.{}", artifact, version, extension),
+    };
+    let mut path: PathBuf = group.split('.').collect();
+    path.push(artifact);
+    path.push(version);
+    path.push(file_name);
+    Ok(path)
+}
+
+fn get_main_class(jar: &Path) -> Result<String, InstallError> {
+    let mut archive = ZipArchive::new(File::open(jar)?)?;
+    let manifest = read_entry(&mut archive, "META-INF/MANIFEST.MF")?;
+    // Manifest lines longer than 72 bytes continue on lines starting with a space.
+    let manifest = manifest.replace("\r\n", "\n").replace("\n ", "");
+    manifest
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|main_class| main_class.trim().to_string())
+        .ok_or_else(|| InstallError::InvalidData(format!("{} no tiene Main-Class", jar.display())))
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<String, InstallError> {
+    let mut contents = String::new();
+    archive.by_name(name)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn extract_entry(
+    archive: &mut ZipArchive<File>,
+    name: &str,
+    target: &Path,
+) -> Result<(), InstallError> {
+    let mut entry = archive.by_name(name)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    io::copy(&mut entry, &mut File::create(target)?)?;
+    Ok(())
+}
+
+fn write_version_json(
+    minecraft_directory: &Path,
+    version_id: &str,
+    contents: &str,
+) -> Result<(), InstallError> {
+    let version_directory = minecraft_directory.join("versions").join(version_id);
+    fs::create_dir_all(&version_directory)?;
+    fs::write(
+        version_directory.join(format!("{}.json", version_id)),
+        contents,
+    )?;
+    log_to_frontend(&format!("Versión {} instalada.", version_id));
+    Ok(())
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::fs_util::write_atomic;
+
+/// The instance's `options.txt`, one `key:value` per line, kept in file order.
+pub struct GameOptions {
+    entries: Vec<(String, String)>,
+}
+
+impl GameOptions {
+    /// Reads `path`, or starts empty when Minecraft has not written it yet.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(GameOptions {
+                entries: Vec::new(),
+            });
+        }
+        let entries = fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        Ok(GameOptions { entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets `key`, returning whether the file content changed.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(entry_key, _)| entry_key == key)
+        {
+            Some((_, entry_value)) if entry_value == value => false,
+            Some((_, entry_value)) => {
+                *entry_value = value.to_string();
+                true
+            }
+            None => {
+                self.entries.push((key.to_string(), value.to_string()));
+                true
+            }
+        }
+    }
+
+    /// Reads a list option such as `resourcePacks`, stored as a JSON array.
+    pub fn get_list(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        self.get(key)
+            .map(|value| {
+                serde_json::from_str(value).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{} no es una lista válida: {}", key, e),
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    pub fn set_list(&mut self, key: &str, values: &[String]) -> Result<bool, Error> {
+        Ok(self.set(key, &serde_json::to_string(values)?))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let contents: String = self
+            .entries
+            .iter()
+            .map(|(key, value)| format!("{}:{}\n", key, value))
+            .collect();
+        write_atomic(path, contents.as_bytes())
+    }
+}
+
+/// Options are plain text, so JSON strings are written without quotes and
+/// everything else (numbers, booleans, lists) as JSON, which is the format
+/// Minecraft uses for values such as `resourcePacks`.
+pub fn to_option_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,112 @@
+use std::{error::Error, fmt, io};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use super::installer_process::InstallerExitError;
+use crate::api_error::ApiError;
+
+#[derive(Debug)]
+pub enum InstallError {
+    Network(String),
+    Java(String),
+    InstallerExit(InstallerExitError),
+    Filesystem(io::Error),
+    ProfileWrite(io::Error),
+    InvalidData(String),
+    Launcher(String),
+}
+
+impl InstallError {
+    fn kind(&self) -> &'static str {
+        match self {
+            InstallError::Network(_) => "network",
+            InstallError::Java(_) => "java",
+            InstallError::InstallerExit(_) => "installer_exit",
+            InstallError::Filesystem(_) => "filesystem",
+            InstallError::ProfileWrite(_) => "profile_write",
+            InstallError::InvalidData(_) => "invalid_data",
+            InstallError::Launcher(_) => "launcher",
+        }
+    }
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallError::Network(e) => write!(f, "Error de red: {}", e),
+            InstallError::Java(e) => write!(f, "Error de Java: {}", e),
+            InstallError::InstallerExit(e) => write!(f, "Error del instalador: {}", e),
+            InstallError::Filesystem(e) => write!(f, "Error de archivos: {}", e),
+            InstallError::ProfileWrite(e) => {
+                write!(f, "Error al escribir el perfil de Minecraft: {}", e)
+            }
+            InstallError::InvalidData(e) => write!(f, "Datos inválidos: {}", e),
+            InstallError::Launcher(e) => write!(f, "Error al abrir el Minecraft Launcher: {}", e),
+        }
+    }
+}
+
+impl Error for InstallError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InstallError::InstallerExit(e) => Some(e),
+            InstallError::Filesystem(e) | InstallError::ProfileWrite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for InstallError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("InstallError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field(
+            "exit_code",
+            &match self {
+                InstallError::InstallerExit(e) => e.code,
+                _ => None,
+            },
+        )?;
+        state.end()
+    }
+}
+
+impl From<reqwest::Error> for InstallError {
+    fn from(e: reqwest::Error) -> Self {
+        InstallError::Network(e.to_string())
+    }
+}
+
+impl From<ApiError> for InstallError {
+    fn from(e: ApiError) -> Self {
+        InstallError::Network(e.to_string())
+    }
+}
+
+impl From<io::Error> for InstallError {
+    fn from(e: io::Error) -> Self {
+        InstallError::Filesystem(e)
+    }
+}
+
+impl From<serde_json::Error> for InstallError {
+    fn from(e: serde_json::Error) -> Self {
+        InstallError::InvalidData(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for InstallError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => InstallError::Filesystem(e),
+            e => InstallError::InvalidData(e.to_string()),
+        }
+    }
+}
+
+impl From<InstallerExitError> for InstallError {
+    fn from(e: InstallerExitError) -> Self {
+        InstallError::InstallerExit(e)
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+use chrono::Local;
+
+use super::install_error::InstallError;
+use crate::{events, log_to_frontend};
+
+#[derive(Debug)]
+pub struct InstallerExitError {
+    pub program: String,
+    pub code: Option<i32>,
+    pub log_path: PathBuf,
+}
+
+impl fmt::Display for InstallerExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "{} terminó con código {}", self.program, code)?,
+            None => write!(f, "{} fue interrumpido", self.program)?,
+        }
+        write!(f, " (registro: {})", self.log_path.display())
+    }
+}
+
+impl Error for InstallerExitError {}
+
+/// Log file shared by every process launched during one loader install.
+pub struct InstallLog {
+    file: File,
+    path: PathBuf,
+}
+
+impl InstallLog {
+    pub fn create(logs_directory: &Path, install_name: &str) -> Result<Self, io::Error> {
+        fs::create_dir_all(logs_directory)?;
+        let path = logs_directory.join(format!(
+            "install-{}-{}.log",
+            install_name,
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        Ok(InstallLog {
+            file: File::create(&path)?,
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs `command` with stdout and stderr merged into one pipe, sending
+    /// every line to the frontend and to the log file as it arrives. The
+    /// process runs on a blocking thread, since Forge processors can take
+    /// minutes.
+    pub async fn run(&mut self, command: Command, program: &str) -> Result<(), InstallError> {
+        let file = self.file.try_clone()?;
+        let sink = events::current_sink();
+        let status = tokio::task::spawn_blocking(move || {
+            events::with_sink_sync(sink, || run_logged(command, file))
+        })
+        .await
+        .map_err(|e| InstallError::Java(format!("{} falló: {}", program, e)))??;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(InstallError::InstallerExit(InstallerExitError {
+                program: program.to_string(),
+                code: status.code(),
+                log_path: self.path.clone(),
+            }))
+        }
+    }
+}
+
+fn run_logged(mut command: Command, mut file: File) -> Result<ExitStatus, InstallError> {
+    writeln!(file, "> {:?}", command)?;
+
+    let (reader, writer) = os_pipe::pipe()?;
+    command.stdout(writer.try_clone()?).stderr(writer);
+    let mut child = command.spawn().map_err(|e| {
+        InstallError::Java(format!(
+            "No se pudo ejecutar {:?}: {}",
+            command.get_program(),
+            e
+        ))
+    })?;
+    // The command still owns the write ends; drop it so the pipe reaches EOF.
+    drop(command);
+
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end();
+        if !text.is_empty() {
+            log_to_frontend(text);
+        }
+        writeln!(file, "{}", text)?;
+        line.clear();
+    }
+
+    let status = child.wait()?;
+    writeln!(file, "< {}", status)?;
+    Ok(status)
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+
+use super::install_error::InstallError;
+use crate::{endpoints::Endpoints, http, log_to_frontend};
+
+const INSTALLED_MARKER: &str = ".canada-runtime";
+
+#[derive(Deserialize)]
+struct RuntimeEntry {
+    manifest: RuntimeManifestRef,
+    version: RuntimeVersion,
+}
+
+#[derive(Deserialize)]
+struct RuntimeManifestRef {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RuntimeVersion {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RuntimeManifest {
+    files: HashMap<String, RuntimeFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum RuntimeFile {
+    Directory,
+    File {
+        #[serde(default)]
+        executable: bool,
+        downloads: RuntimeDownloads,
+    },
+    Link {
+        #[cfg_attr(not(unix), allow(dead_code))]
+        target: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct RuntimeDownloads {
+    raw: RuntimeDownload,
+}
+
+#[derive(Deserialize)]
+struct RuntimeDownload {
+    url: String,
+    sha1: String,
+}
+
+fn get_platform() -> &'static str {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("windows", "x86_64") => "windows-x64",
+        ("windows", "aarch64") => "windows-arm64",
+        ("windows", _) => "windows-x86",
+        ("macos", "aarch64") => "mac-os-arm64",
+        ("macos", _) => "mac-os",
+        (_, "x86") => "linux-i386",
+        _ => "linux",
+    }
+}
+
+pub fn get_java_executable(runtime_directory: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        runtime_directory.join("bin").join("java.exe")
+    } else if cfg!(target_os = "macos") {
+        runtime_directory.join("jre.bundle/Contents/Home/bin/java")
+    } else {
+        runtime_directory.join("bin").join("java")
+    }
+}
+
+/// Java executable of the runtime `component` when it is fully installed in
+/// `runtimes_directory`.
+pub fn installed_runtime(component: &str, runtimes_directory: &Path) -> Option<PathBuf> {
+    let runtime_directory = runtimes_directory.join(component);
+    let java = get_java_executable(&runtime_directory);
+    (runtime_directory.join(INSTALLED_MARKER).exists() && java.exists()).then_some(java)
+}
+
+/// Downloads the Mojang Java runtime `component` into `runtimes_directory`
+/// unless it is already there, and returns the path of its java executable.
+pub async fn ensure_runtime(
+    component: &str,
+    runtimes_directory: &Path,
+) -> Result<PathBuf, InstallError> {
+    if let Some(java) = installed_runtime(component, runtimes_directory) {
+        return Ok(java);
+    }
+    let runtime_directory = runtimes_directory.join(component);
+    let java = get_java_executable(&runtime_directory);
+
+    let index: HashMap<String, HashMap<String, Vec<RuntimeEntry>>> =
+        http::get_json(&Endpoints::current().java_runtimes()).await?;
+    let entry = index
+        .get(get_platform())
+        .and_then(|components| components.get(component))
+        .and_then(|entries| entries.first())
+        .ok_or_else(|| {
+            InstallError::Java(format!(
+                "Java {} no está disponible para {}",
+                component,
+                get_platform()
+            ))
+        })?;
+
+    log_to_frontend(&format!(
+        "Descargando Java {} ({})... ☕",
+        entry.version.name, component
+    ));
+    let manifest: RuntimeManifest = http::get_json(&entry.manifest.url).await?;
+
+    for (path, file) in &manifest.files {
+        let target = runtime_directory.join(path);
+        match file {
+            RuntimeFile::Directory => fs::create_dir_all(&target)?,
+            RuntimeFile::File {
+                executable,
+                downloads,
+            } => {
+                if target.exists() && file_sha1(&target)? == downloads.raw.sha1 {
+                    continue;
+                }
+                let bytes = http::get_bytes(&downloads.raw.url).await?;
+                if format!("{:x}", Sha1::digest(&bytes)) != downloads.raw.sha1 {
+                    return Err(InstallError::InvalidData(format!(
+                        "El archivo {} de Java está corrupto",
+                        path
+                    )));
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&target, &bytes)?;
+                if *executable {
+                    set_executable(&target)?;
+                }
+            }
+            RuntimeFile::Link { .. } => {}
+        }
+    }
+
+    #[cfg(unix)]
+    for (path, file) in &manifest.files {
+        if let RuntimeFile::Link { target } = file {
+            let link = runtime_directory.join(path);
+            if let Some(parent) = link.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let _ = fs::remove_file(&link);
+            std::os::unix::fs::symlink(target, &link)?;
+        }
+    }
+
+    fs::write(
+        runtime_directory.join(INSTALLED_MARKER),
+        &entry.version.name,
+    )?;
+    log_to_frontend(&format!("Java instalado en: {}", java.display()));
+    Ok(java)
+}
+
+pub fn file_sha1(path: &Path) -> Result<String, std::io::Error> {
+    let bytes = fs::read(path)?;
+    Ok(format!("{:x}", Sha1::digest(&bytes)))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<(), std::io::Error> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<(), std::io::Error> {
+    Ok(())
+}
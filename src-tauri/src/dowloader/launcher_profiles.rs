@@ -0,0 +1,112 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{fs_util::write_atomic, log_to_frontend};
+
+const BACKUP_MARKER: &str = ".canada-backup-";
+const KEPT_BACKUPS: usize = 10;
+
+/// Reads `launcher_profiles.json`. A missing file is an empty profile list,
+/// but a file that does not parse is an error: rewriting it would wipe every
+/// profile and setting the player has.
+pub fn read_launcher_profiles(path: &Path) -> Result<serde_json::Value, Error> {
+    if !path.exists() {
+        return Ok(serde_json::json!({"profiles": {}}));
+    }
+    let file_contents = fs::read_to_string(path)?;
+    let profiles: serde_json::Value = serde_json::from_str(&file_contents).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} no es un JSON válido ({}), no se ha modificado",
+                path.display(),
+                e
+            ),
+        )
+    })?;
+    if !profiles.is_object() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} no contiene un objeto JSON", path.display()),
+        ));
+    }
+    Ok(profiles)
+}
+
+/// Backs up the current file and replaces it atomically.
+pub fn write_launcher_profiles(path: &Path, profiles: &serde_json::Value) -> Result<(), Error> {
+    if path.exists() {
+        let backup = backup_launcher_profiles(path)?;
+        log_to_frontend(&format!("Copia de seguridad creada: {}", backup.display()));
+    }
+
+    write_atomic(path, serde_json::to_string_pretty(profiles)?.as_bytes())?;
+
+    prune_backups(path);
+    Ok(())
+}
+
+fn backup_launcher_profiles(path: &Path) -> Result<PathBuf, Error> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(
+        "{}{}",
+        BACKUP_MARKER,
+        Local::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Backups of `path`, newest first.
+pub fn list_backups(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let (Some(directory), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Ok(Vec::new());
+    };
+    let prefix = format!("{}{}", file_name.to_string_lossy(), BACKUP_MARKER);
+    let mut backups: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|entry| entry.path())
+        .collect();
+    // The timestamp suffix sorts chronologically.
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Restores the backup named `backup_name`, or the newest one, returning the
+/// path that was restored. The current file is backed up first.
+pub fn restore_backup(path: &Path, backup_name: Option<&str>) -> Result<PathBuf, Error> {
+    let backups = list_backups(path)?;
+    let backup = match backup_name {
+        Some(name) => backups
+            .into_iter()
+            .find(|backup| backup.file_name().is_some_and(|file| file == name)),
+        None => backups.into_iter().next(),
+    }
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "No hay ninguna copia de seguridad de los perfiles",
+        )
+    })?;
+
+    let profiles = read_launcher_profiles(&backup)?;
+    write_launcher_profiles(path, &profiles)?;
+    log_to_frontend(&format!("Perfiles restaurados desde {}", backup.display()));
+    Ok(backup)
+}
+
+fn prune_backups(path: &Path) {
+    if let Ok(backups) = list_backups(path) {
+        for old_backup in backups.iter().skip(KEPT_BACKUPS) {
+            let _ = fs::remove_file(old_backup);
+        }
+    }
+}
@@ -1,46 +1,66 @@
 use std::{
-    env,
-    error::Error,
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
     process::Command,
 };
 
-use crate::{data_structs::ModLoaders, log_to_frontend};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    data_structs::{self, MinecraftVersionDetails, ModLoaders},
+    endpoints::Endpoints,
+    fs_util::create_temp_directory,
+    http, log_to_frontend,
+};
+
+use super::{
+    forge_installer, install_error::InstallError, installer_process::InstallLog, java_runtime,
+};
 
 pub struct LoaderInstaller {
     pub loader: ModLoaders,
     pub minecraft_version: String,
     pub loader_version: String,
     pub minecraft_directory: PathBuf,
+    pub runtime_directory: PathBuf,
+    pub logs_directory: PathBuf,
 }
 
 impl LoaderInstaller {
-    pub async fn install_loader(&self) {
+    pub async fn install_loader(&self) -> Result<(), InstallError> {
         if self.is_version_installed() {
             log_to_frontend(&format!(
                 "{} versión {} ya está instalada, omitiendo instalación.",
                 match self.loader {
                     ModLoaders::Forge => "Forge",
                     ModLoaders::Fabric => "Fabric",
+                    ModLoaders::Vanilla => "Minecraft",
                 },
-                self.loader_version
+                match self.loader {
+                    ModLoaders::Vanilla => &self.minecraft_version,
+                    _ => &self.loader_version,
+                }
             ));
-            return;
+            return Ok(());
         }
         match self.loader {
             ModLoaders::Forge => {
                 log_to_frontend("Instalando Forge... ⚙️");
-                if let Err(e) = self.install_forge().await {
+                self.install_forge().await.inspect_err(|e| {
                     log_to_frontend(&format!("La instalación de Forge falló: {}", e));
-                }
+                })
             }
             ModLoaders::Fabric => {
                 log_to_frontend("Instalando Fabric... ⚙️");
-                if let Err(e) = self.install_fabric().await {
+                self.install_fabric().await.inspect_err(|e| {
                     log_to_frontend(&format!("La instalación de Fabric falló: {}", e));
-                }
+                })
+            }
+            ModLoaders::Vanilla => {
+                log_to_frontend("Instalando Minecraft Vanilla... ⚙️");
+                self.install_vanilla().await.inspect_err(|e| {
+                    log_to_frontend(&format!("La instalación de Minecraft falló: {}", e));
+                })
             }
         }
     }
@@ -51,58 +71,116 @@ impl LoaderInstaller {
                 format!("{}-forge-{}", self.minecraft_version, self.loader_version)
             }
             ModLoaders::Fabric => format!("fabric-loader-{}-{}", "0.16.10", self.minecraft_version),
+            ModLoaders::Vanilla => self.minecraft_version.clone(),
         }
     }
 
-    fn is_version_installed(&self) -> bool {
-        let mut versions_path = self.minecraft_directory.clone();
-        versions_path.push("versions");
-        versions_path.push(self.get_version_format());
-        versions_path.exists()
+    /// Whether the version json is in place, and for vanilla the client jar
+    /// too. An interrupted install leaves the version folder without them.
+    pub fn is_version_installed(&self) -> bool {
+        let version = self.get_version_format();
+        let version_directory = self.minecraft_directory.join("versions").join(&version);
+        let json_installed = version_directory
+            .join(format!("{}.json", version))
+            .is_file();
+        json_installed
+            && (self.loader != ModLoaders::Vanilla
+                || version_directory.join(format!("{}.jar", version)).is_file())
     }
-    async fn download_installer(&self) -> Result<(), Box<dyn Error>> {
+
+    /// Downloads the loader's installer into a temporary directory of its
+    /// own, returning the jar. The caller removes its directory.
+    async fn download_installer(&self) -> Result<PathBuf, InstallError> {
         log_to_frontend(&format!(
             "Descargando instalador: {}",
             self.get_installer_url()
         ));
-        let response = reqwest::get(self.get_installer_url())
-            .await?
-            .bytes()
-            .await?;
-        let mut file = File::create(Self::get_temp_path())?;
-        file.write_all(&response)?;
-        log_to_frontend(&format!("Descargado: {}", Self::get_temp_path()));
-        Ok(())
+        let response = http::get_bytes(&self.get_installer_url()).await?;
+        let installer_path = create_temp_directory("canada_installer")?.join("installer.jar");
+        fs::write(&installer_path, &response)?;
+        log_to_frontend(&format!("Descargado: {}", installer_path.display()));
+        Ok(installer_path)
+    }
+
+    fn remove_installer(installer_path: &Path) {
+        if let Some(directory) = installer_path.parent() {
+            let _ = fs::remove_dir_all(directory);
+        }
     }
 
     fn get_installer_url(&self) -> String {
         match self.loader {
-            ModLoaders::Fabric => {
-                format!(
-                    "https://maven.fabricmc.net/net/fabricmc/fabric-installer/{0}/fabric-installer-{0}.jar",
-                    self.loader_version
-                )
-            }
-            ModLoaders::Forge => {
-                let full_version = format!("{}-{}", self.minecraft_version, self.loader_version);
-                format!(
-                    "https://maven.minecraftforge.net/net/minecraftforge/forge/{}/forge-{}-installer.jar",
-                    full_version, full_version
-                )
-            }
+            ModLoaders::Fabric => Endpoints::current().fabric_installer_jar(&self.loader_version),
+            ModLoaders::Forge => Endpoints::current().forge_installer_jar(&format!(
+                "{}-{}",
+                self.minecraft_version, self.loader_version
+            )),
+            ModLoaders::Vanilla => String::new(),
         }
     }
 
-    fn get_temp_path() -> String {
-        let save_path = env::temp_dir().join("fabric_installer.jar");
-        save_path
-            .to_str()
-            .unwrap_or("C:/temp/fabric_installer.jar")
-            .to_string()
+    async fn install_vanilla(&self) -> Result<(), InstallError> {
+        let manifest = data_structs::get_minecraft_manifest().await?;
+        let version = manifest
+            .versions
+            .into_iter()
+            .find(|version| version.id == self.minecraft_version)
+            .ok_or_else(|| {
+                InstallError::InvalidData(format!(
+                    "La versión {} no existe en el manifiesto de Mojang",
+                    self.minecraft_version
+                ))
+            })?;
+
+        log_to_frontend(&format!(
+            "Descargando versión de Minecraft: {}",
+            version.url
+        ));
+        let version_json = http::get_bytes(&version.url).await?;
+        let details: MinecraftVersionDetails = serde_json::from_slice(&version_json)?;
+
+        log_to_frontend(&format!(
+            "Descargando cliente de Minecraft: {}",
+            details.downloads.client.url
+        ));
+        let client_jar = http::get_bytes(&details.downloads.client.url).await?;
+        if client_jar.len() as u64 != details.downloads.client.size {
+            return Err(InstallError::Network(format!(
+                "El cliente descargado tiene {} bytes, se esperaban {}",
+                client_jar.len(),
+                details.downloads.client.size
+            )));
+        }
+        if format!("{:x}", Sha1::digest(&client_jar)) != details.downloads.client.sha1 {
+            return Err(InstallError::InvalidData(format!(
+                "El cliente de Minecraft {} está corrupto",
+                details.id
+            )));
+        }
+
+        // The json goes last: `is_version_installed` needs both, so a write
+        // that fails halfway is retried on the next run.
+        let version_directory = self.minecraft_directory.join("versions").join(&details.id);
+        fs::create_dir_all(&version_directory)?;
+        fs::write(
+            version_directory.join(format!("{}.jar", details.id)),
+            &client_jar,
+        )?;
+        fs::write(
+            version_directory.join(format!("{}.json", details.id)),
+            &version_json,
+        )?;
+
+        log_to_frontend("Instalación de Minecraft Vanilla exitosa.");
+        Ok(())
     }
 
-    async fn install_fabric(&self) -> Result<(), Box<dyn Error>> {
-        self.download_installer().await?;
+    /// Runs the Fabric installer with the system `java`. Unlike Forge it does
+    /// not use the managed runtime: the installer runs on any Java 8 or newer
+    /// and needs no vanilla install first. Mirrored endpoints only redirect
+    /// its metadata and maven, so Java still has to be installed.
+    async fn install_fabric(&self) -> Result<(), InstallError> {
+        let installer_path = self.download_installer().await?;
 
         log_to_frontend("Instalando Fabric...");
 
@@ -115,77 +193,144 @@ impl LoaderInstaller {
         let command_str = format!(
             "{} -jar \"{}\" client -dir \"{}\" -mcversion {}",
             java_command,
-            Self::get_temp_path(),
+            installer_path.display(),
             self.minecraft_directory.display(),
             self.minecraft_version
         );
 
         log_to_frontend(&format!("Ejecutando comando: {}", command_str));
 
-        let status = Command::new(java_command)
+        let mut install_log = InstallLog::create(&self.logs_directory, &self.get_version_format())?;
+        log_to_frontend(&format!(
+            "Registro de instalación: {}",
+            install_log.path().display()
+        ));
+
+        let mut command = Command::new(java_command);
+        command
             .arg("-jar")
-            .arg(Self::get_temp_path())
+            .arg(&installer_path)
             .arg("client")
             .arg("-dir")
             .arg(self.minecraft_directory.clone())
             .arg("-mcversion")
-            .arg(self.minecraft_version.clone())
-            .status()?;
-
-        if status.success() {
-            log_to_frontend("Instalación de Fabric exitosa.");
-            let _ = fs::remove_file(Self::get_temp_path());
-        } else {
-            log_to_frontend("Error al instalar Fabric.");
+            .arg(self.minecraft_version.clone());
+        let endpoints = Endpoints::current();
+        let defaults = Endpoints::default();
+        if endpoints.fabric_meta != defaults.fabric_meta {
+            command
+                .arg("-metaurl")
+                .arg(format!("{}/", endpoints.fabric_meta.trim_end_matches('/')));
         }
+        if endpoints.fabric_maven != defaults.fabric_maven {
+            command
+                .arg("-mavenurl")
+                .arg(format!("{}/", endpoints.fabric_maven.trim_end_matches('/')));
+        }
+        let result = install_log.run(command, "El instalador de Fabric").await;
+        Self::remove_installer(&installer_path);
+        result?;
 
+        log_to_frontend("Instalación de Fabric exitosa.");
         Ok(())
     }
 
-    async fn install_forge(&self) -> Result<(), Box<dyn Error>> {
+    async fn install_forge(&self) -> Result<(), InstallError> {
         log_to_frontend(&format!(
             "Intentando descargar el instalador de Forge desde: {}",
             self.get_installer_url()
         ));
 
-        if let Err(e) = self.download_installer().await {
+        let installer_path = self.download_installer().await.inspect_err(|e| {
             log_to_frontend(&format!(
                 "No se pudo descargar el instalador de Forge: {}",
                 e
             ));
-            return Err(e);
-        }
+        })?;
 
         log_to_frontend("Instalador de Forge descargado exitosamente.");
-        log_to_frontend("Ejecutando el instalador de Forge...");
+        let result = self.run_forge_installer(&installer_path).await;
+        Self::remove_installer(&installer_path);
+        result?;
 
-        let java_command = if cfg!(target_os = "windows") {
-            "java.exe"
-        } else {
-            "java"
-        };
+        log_to_frontend("Instalación de Forge completada exitosamente.");
+        Ok(())
+    }
+
+    async fn run_forge_installer(&self, installer_path: &Path) -> Result<(), InstallError> {
+        if !self.is_vanilla_installed() {
+            log_to_frontend("Forge necesita Minecraft Vanilla, instalándolo primero...");
+            self.install_vanilla().await?;
+        }
+        let java = self.get_managed_java(false).await?;
 
+        let mut install_log = InstallLog::create(&self.logs_directory, &self.get_version_format())?;
+        log_to_frontend(&format!(
+            "Ejecutando el instalador de Forge con Java: {}",
+            java.display()
+        ));
         log_to_frontend(&format!(
-            "Ejecutando instalador de paquete Java: {} {}",
-            Self::get_temp_path(),
-            "--installClient"
+            "Registro de instalación: {}",
+            install_log.path().display()
         ));
+        forge_installer::install(
+            installer_path,
+            &self.minecraft_directory,
+            &self.minecraft_version,
+            &java,
+            &mut install_log,
+        )
+        .await
+    }
 
-        let status = Command::new(java_command)
-            .arg("-jar")
-            .arg(Self::get_temp_path())
-            .arg("--installClient")
-            .arg(self.minecraft_directory.clone())
-            .status()?;
+    fn get_vanilla_directory(&self) -> PathBuf {
+        let mut vanilla_directory = self.minecraft_directory.clone();
+        vanilla_directory.push("versions");
+        vanilla_directory.push(&self.minecraft_version);
+        vanilla_directory
+    }
 
-        if status.success() {
-            log_to_frontend("Instalación exitosa.");
-            let _ = fs::remove_file(Self::get_temp_path());
-        } else {
-            log_to_frontend("Error al instalar.");
-        }
+    fn is_vanilla_installed(&self) -> bool {
+        let vanilla_directory = self.get_vanilla_directory();
+        vanilla_directory
+            .join(format!("{}.json", self.minecraft_version))
+            .exists()
+            && vanilla_directory
+                .join(format!("{}.jar", self.minecraft_version))
+                .exists()
+    }
 
-        log_to_frontend("Instalación de Forge completada exitosamente.");
-        Ok(())
+    /// The Mojang Java runtime this version asks for, downloading the
+    /// version and the runtime when they are missing. With `offline` only an
+    /// installed runtime is used.
+    pub async fn get_managed_java(&self, offline: bool) -> Result<PathBuf, InstallError> {
+        if !self.is_vanilla_installed() {
+            if offline {
+                return Err(InstallError::Java(format!(
+                    "Modo sin conexión: Minecraft {} no está descargado, no se sabe qué Java usar. Conéctate o elige otro Java",
+                    self.minecraft_version
+                )));
+            }
+            self.install_vanilla().await?;
+        }
+        let version_json = fs::read_to_string(
+            self.get_vanilla_directory()
+                .join(format!("{}.json", self.minecraft_version)),
+        )?;
+        let details: MinecraftVersionDetails = serde_json::from_str(&version_json)?;
+        let component = details
+            .java_version
+            .map(|java_version| java_version.component)
+            .unwrap_or_else(|| "jre-legacy".to_string());
+        if offline {
+            return java_runtime::installed_runtime(&component, &self.runtime_directory)
+                .ok_or_else(|| {
+                    InstallError::Java(format!(
+                        "Modo sin conexión: Java {} no está descargado. Conéctate o elige otro Java",
+                        component
+                    ))
+                });
+        }
+        java_runtime::ensure_runtime(&component, &self.runtime_directory).await
     }
 }
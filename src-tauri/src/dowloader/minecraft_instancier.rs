@@ -1,16 +1,86 @@
-use std::{fs, io::Error, path::PathBuf};
+use std::{
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
 
 use base64::{engine::general_purpose, Engine};
 use chrono::Utc;
 
-use crate::{data_structs::MinecraftProfile, log_to_frontend};
+use super::{
+    game_options::{to_option_value, GameOptions},
+    launcher_profiles::{read_launcher_profiles, write_launcher_profiles},
+    DEFAULT_INSTANCE_ID,
+};
+use crate::{
+    data_structs::{MinecraftProfile, PackOptions, ProfileResolution},
+    log_to_frontend,
+    memory_settings::MemorySettings,
+};
 
 pub struct MinecraftInstancier {
+    pub instance_id: String,
     pub minecraft_canada_directory: PathBuf,
     pub loader_version: String,
     pub launcher_profiles_directory: PathBuf,
+    /// Heap and JVM flags saved for the instance. Without them an existing
+    /// profile keeps its javaArgs and a new one gets the recommended heap.
+    pub java_args: Option<String>,
+    pub java_dir: Option<PathBuf>,
+    pub resolution: Option<ProfileResolution>,
+    /// Icon from the pack manifest, replacing the embedded one.
+    pub icon: Option<String>,
+    pub options: PackOptions,
+    pub resource_packs: Option<Vec<String>>,
 }
 static ICON: &[u8] = include_bytes!("../../canada.png");
+const LEGACY_PROFILE_KEY: &str = "Modded Profile";
+/// Resource packs enabled by the launcher on the last install, so packs
+/// removed from the bucket can be disabled without touching the player's.
+const MANAGED_RESOURCE_PACKS_FILE: &str = ".canada-resourcepacks.json";
+/// Created once the pack's `options.txt` defaults were applied.
+const OPTIONS_APPLIED_MARKER: &str = ".canada-options-applied";
+const MANAGED_PROFILE_KEYS: [&str; 6] = [
+    "name",
+    "type",
+    "gameDir",
+    "lastVersionId",
+    "icon",
+    "lastUsed",
+];
+
+pub fn get_profile_key(instance_id: &str) -> String {
+    format!("canada-{}", instance_id)
+}
+
+/// Name shown in the Minecraft Launcher, telling the instances apart.
+fn get_profile_name(instance_id: &str) -> String {
+    if instance_id == DEFAULT_INSTANCE_ID {
+        "Canada Mods".to_string()
+    } else {
+        format!("Canada Mods ({})", instance_id)
+    }
+}
+
+/// Removes the launcher profile of `instance_id`, returning whether it existed.
+pub fn remove_minecraft_profile(
+    launcher_profiles_directory: &Path,
+    instance_id: &str,
+) -> Result<bool, Error> {
+    if !launcher_profiles_directory.exists() {
+        return Ok(false);
+    }
+    let mut profiles = read_launcher_profiles(launcher_profiles_directory)?;
+    let removed = profiles["profiles"]
+        .as_object_mut()
+        .and_then(|profiles_map| profiles_map.remove(&get_profile_key(instance_id)))
+        .is_some();
+    if removed {
+        write_launcher_profiles(launcher_profiles_directory, &profiles)?;
+        log_to_frontend("Perfil de Minecraft eliminado.");
+    }
+    Ok(removed)
+}
 
 impl MinecraftInstancier {
     pub fn create_minecraft_instance(&self) -> Result<(), Error> {
@@ -23,52 +93,204 @@ impl MinecraftInstancier {
                 self.minecraft_canada_directory.display()
             ));
         }
-        let profiles: serde_json::Value = if self.launcher_profiles_directory.exists() {
-            let file_contents = fs::read_to_string(&self.launcher_profiles_directory)?;
-            serde_json::from_str(&file_contents)
-                .unwrap_or_else(|_| serde_json::json!({"profiles": {}}))
-        } else {
-            serde_json::json!({"profiles": {}})
-        };
-
-        let mut profiles_obj = profiles.clone();
+        let mut profiles_obj = read_launcher_profiles(&self.launcher_profiles_directory)?;
 
         let new_profile = MinecraftProfile {
-            name: "Canada Mods".to_string(),
+            name: get_profile_name(&self.instance_id),
             game_dir: self
                 .minecraft_canada_directory
                 .to_string_lossy()
                 .to_string(),
             version: self.loader_version.clone(),
-            java_args: Some("-Xmx7G".to_string()),
-            icon: Self::get_encoded_icon(),
+            java_args: Some(
+                self.java_args
+                    .clone()
+                    .unwrap_or_else(|| MemorySettings::default().to_java_args()),
+            ),
+            icon: self.get_encoded_icon(),
             last_used: Self::get_now_time(),
+            profile_type: "custom".to_string(),
+            created: Self::get_now_time(),
+            java_dir: self
+                .java_dir
+                .as_ref()
+                .map(|java_dir| java_dir.to_string_lossy().to_string()),
+            resolution: self.resolution,
         };
 
-        println!(
-            "Generated Profile JSON: {}",
-            serde_json::to_string_pretty(&new_profile)?
-        );
-
         let profile_json = serde_json::to_value(&new_profile)?;
+        let profile_key = get_profile_key(&self.instance_id);
 
+        if !profiles_obj["profiles"].is_object() {
+            profiles_obj["profiles"] = serde_json::json!({});
+        }
         if let Some(profiles_map) = profiles_obj["profiles"].as_object_mut() {
-            profiles_map.insert("Modded Profile".to_string(), profile_json);
-        } else {
-            profiles_obj["profiles"] = serde_json::json!({
-                "Modded Profile": profile_json
-            });
+            if !profiles_map.contains_key(&profile_key) {
+                if let Some(legacy_profile) = self.take_legacy_profile(profiles_map) {
+                    log_to_frontend("Migrando el perfil anterior de Canada Mods.");
+                    profiles_map.insert(profile_key.clone(), legacy_profile);
+                }
+            }
+
+            match profiles_map
+                .get_mut(&profile_key)
+                .and_then(|profile| profile.as_object_mut())
+            {
+                Some(existing_profile) => {
+                    // Only refresh what the launcher owns so the player's own
+                    // tweaks (javaArgs, resolution...) survive every update.
+                    if let Some(java_args) = &self.java_args {
+                        existing_profile.insert("javaArgs".to_string(), java_args.clone().into());
+                    }
+                    for key in MANAGED_PROFILE_KEYS {
+                        match profile_json.get(key) {
+                            Some(value) => {
+                                existing_profile.insert(key.to_string(), value.clone());
+                            }
+                            None => {
+                                existing_profile.remove(key);
+                            }
+                        }
+                    }
+                    // Without a Java or resolution of our own, keep the player's.
+                    for key in ["javaDir", "resolution"] {
+                        if let Some(value) = profile_json.get(key) {
+                            existing_profile.insert(key.to_string(), value.clone());
+                        }
+                    }
+                    existing_profile
+                        .entry("created")
+                        .or_insert_with(|| profile_json["created"].clone());
+                }
+                None => {
+                    profiles_map.insert(profile_key, profile_json);
+                }
+            }
         }
 
-        let updated_profiles = serde_json::to_string_pretty(&profiles_obj)?;
-        fs::write(&self.launcher_profiles_directory, updated_profiles)?;
+        write_launcher_profiles(&self.launcher_profiles_directory, &profiles_obj)?;
 
         log_to_frontend("Perfil de Minecraft creado exitosamente con una instancia separada.");
 
         Ok(())
     }
 
-    fn get_encoded_icon() -> String {
+    /// Applies the pack's `options.txt` defaults. The first install writes
+    /// every default the file does not set yet; afterwards only the enforced
+    /// keys are written, so the player's keybinds and video settings stay
+    /// theirs. The first install is told by a marker rather than by the file
+    /// existing, since `enable_resource_packs` or Minecraft may create it
+    /// before the defaults are applied.
+    pub fn apply_game_options(&self) -> Result<(), Error> {
+        if self.options.defaults.is_empty() {
+            return Ok(());
+        }
+        let options_path = self.minecraft_canada_directory.join("options.txt");
+        let marker_path = self.minecraft_canada_directory.join(OPTIONS_APPLIED_MARKER);
+        let first_install = !marker_path.exists();
+        let mut game_options = GameOptions::read(&options_path)?;
+
+        let mut changed = false;
+        for (key, value) in &self.options.defaults {
+            let missing = game_options.get(key).is_none();
+            if (first_install && missing) || self.options.enforced.contains(key) {
+                changed |= game_options.set(key, &to_option_value(value));
+            }
+        }
+        if changed {
+            game_options.write(&options_path)?;
+            log_to_frontend("Opciones de Minecraft actualizadas.");
+        }
+        if first_install {
+            fs::write(marker_path, "")?;
+        }
+        Ok(())
+    }
+
+    /// Enables the synced resource packs in `options.txt` and disables the
+    /// ones the launcher enabled before that are no longer in the pack.
+    pub fn enable_resource_packs(&self) -> Result<(), Error> {
+        let resource_packs_directory = self.minecraft_canada_directory.join("resourcepacks");
+        let pack_names = match &self.resource_packs {
+            Some(pack_names) => pack_names.clone(),
+            None => Self::get_synced_resource_packs(&resource_packs_directory)?,
+        };
+        let pack_ids: Vec<String> = pack_names
+            .iter()
+            .filter(|name| resource_packs_directory.join(name).exists())
+            .map(|name| format!("file/{}", name))
+            .collect();
+
+        let managed_path = self
+            .minecraft_canada_directory
+            .join(MANAGED_RESOURCE_PACKS_FILE);
+        let previously_managed: Vec<String> = fs::read_to_string(&managed_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        if pack_ids.is_empty() && previously_managed.is_empty() {
+            return Ok(());
+        }
+
+        let options_path = self.minecraft_canada_directory.join("options.txt");
+        let mut game_options = GameOptions::read(&options_path)?;
+        let mut enabled = game_options
+            .get_list("resourcePacks")?
+            .unwrap_or_else(|| vec!["vanilla".to_string()]);
+        enabled.retain(|id| !previously_managed.contains(id) && !pack_ids.contains(id));
+        // options.txt lists packs from the bottom up, so the top one goes last.
+        enabled.extend(pack_ids.iter().rev().cloned());
+
+        if game_options.set_list("resourcePacks", &enabled)? {
+            game_options.write(&options_path)?;
+            log_to_frontend(&format!(
+                "Paquetes de recursos activados: {}",
+                pack_names.join(", ")
+            ));
+        }
+        fs::write(managed_path, serde_json::to_string(&pack_ids)?)
+    }
+
+    fn get_synced_resource_packs(resource_packs_directory: &Path) -> Result<Vec<String>, Error> {
+        if !resource_packs_directory.exists() {
+            return Ok(Vec::new());
+        }
+        let mut pack_names: Vec<String> = fs::read_dir(resource_packs_directory)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        pack_names.sort();
+        Ok(pack_names)
+    }
+
+    /// The profile written before instances had their own keys, when it
+    /// points at this instance's game directory. Only the default instance
+    /// keeps that directory, so only it can take the profile over.
+    fn take_legacy_profile(
+        &self,
+        profiles_map: &mut serde_json::Map<String, serde_json::Value>,
+    ) -> Option<serde_json::Value> {
+        let game_dir = self.minecraft_canada_directory.to_string_lossy();
+        let is_ours = profiles_map
+            .get(LEGACY_PROFILE_KEY)
+            .and_then(|profile| profile.get("gameDir"))
+            .and_then(|dir| dir.as_str())
+            .is_some_and(|dir| dir == game_dir);
+        if is_ours {
+            profiles_map.remove(LEGACY_PROFILE_KEY)
+        } else {
+            None
+        }
+    }
+
+    fn get_encoded_icon(&self) -> String {
+        if let Some(icon) = self.icon.as_deref().filter(|icon| !icon.is_empty()) {
+            if icon.starts_with("data:") {
+                return icon.to_string();
+            }
+            return format!("data:image/png;base64,{}", icon);
+        }
         let encoder = general_purpose::STANDARD;
         let base64_encoded_icon = encoder.encode(ICON);
         format!("data:image/png;base64,{}", base64_encoded_icon)
@@ -0,0 +1,300 @@
+//! Publishes a local pack tree to the buckets the launcher syncs from:
+//! `mods/` and `config/` go to the mods bucket, `resourcepacks/` to the
+//! resource packs one. Only changed files are uploaded, objects missing from
+//! the tree are removed and `pack.json` lists the hash of every file.
+//! Players' launchers do not sync the configs; they are kept under `config/`
+//! for servers and tools.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use sha1::{Digest, Sha1};
+
+use super::{get_clients, get_pack_manifest, InstallError, CONFIG_PREFIX, PACK_MANIFEST_KEY};
+use crate::{
+    data_structs::{PackFile, PackManifest},
+    log_to_frontend,
+};
+
+/// What a publish changed, or would change on a dry run. Objects are named
+/// `bucket/key`.
+#[derive(Debug, Default)]
+pub struct PublishReport {
+    pub uploaded: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// A folder of the tree and where it is published.
+struct Folder {
+    name: &'static str,
+    /// Index of the bucket in `get_clients`.
+    bucket: usize,
+    key_prefix: &'static str,
+    /// Subfolders are published too. The mods and resource packs folders are
+    /// synced flat.
+    recursive: bool,
+    /// When missing the published files are left alone instead of removed.
+    optional: bool,
+}
+
+const FOLDERS: [Folder; 3] = [
+    Folder {
+        name: "mods",
+        bucket: 0,
+        key_prefix: "",
+        recursive: false,
+        optional: false,
+    },
+    Folder {
+        name: "config",
+        bucket: 0,
+        key_prefix: CONFIG_PREFIX,
+        recursive: true,
+        optional: true,
+    },
+    Folder {
+        name: "resourcepacks",
+        bucket: 1,
+        key_prefix: "",
+        recursive: false,
+        optional: false,
+    },
+];
+
+struct LocalFile {
+    bucket: usize,
+    key: String,
+    local_path: PathBuf,
+    file: PackFile,
+}
+
+/// Publishes the `mods/`, `resourcepacks/` and `config/` folders of `root`.
+/// `root/pack.json`, when present, provides the icon, servers, options and
+/// resource packs of the manifest; otherwise the published ones are kept.
+/// With `dry_run` only the changes are reported.
+pub async fn publish_pack(root: &Path, dry_run: bool) -> Result<PublishReport, InstallError> {
+    let mut local_files = Vec::new();
+    let mut managed = Vec::new();
+    for folder in &FOLDERS {
+        let directory = root.join(folder.name);
+        if !directory.is_dir() {
+            if folder.optional {
+                log_to_frontend(&format!(
+                    "No hay carpeta {}, se mantiene la publicada.",
+                    folder.name
+                ));
+                continue;
+            }
+            return Err(InstallError::InvalidData(format!(
+                "Falta la carpeta {}",
+                directory.display()
+            )));
+        }
+        for relative in list_files(&directory, folder.recursive)? {
+            let local_path = directory.join(&relative);
+            let bytes = fs::read(&local_path)?;
+            local_files.push(LocalFile {
+                bucket: folder.bucket,
+                key: format!("{}{}", folder.key_prefix, relative),
+                local_path,
+                file: PackFile {
+                    path: format!("{}/{}", folder.name, relative),
+                    sha1: format!("{:x}", Sha1::digest(&bytes)),
+                    size: bytes.len() as u64,
+                },
+            });
+        }
+        managed.push(folder);
+    }
+
+    let local_manifest = root.join(PACK_MANIFEST_KEY);
+    let local_manifest = if local_manifest.exists() {
+        Some(serde_json::from_slice::<PackManifest>(&fs::read(
+            &local_manifest,
+        )?)?)
+    } else {
+        None
+    };
+
+    let (clients, buckets) = get_clients();
+    let published = get_pack_manifest(&clients[0], &buckets[0]).await?;
+    let published_hashes: HashMap<&str, &str> = published
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file.sha1.as_str()))
+        .collect();
+
+    let mut report = PublishReport::default();
+    let mut uploads = Vec::new();
+    let mut deletions = Vec::new();
+    for (index, (client, bucket)) in clients.iter().zip(&buckets).enumerate() {
+        let remote_sizes = list_objects(client, bucket).await?;
+        let bucket_files: Vec<_> = local_files.iter().filter(|f| f.bucket == index).collect();
+
+        for local in &bucket_files {
+            let unchanged = remote_sizes.get(&local.key) == Some(&(local.file.size as i64))
+                && published_hashes.get(local.file.path.as_str())
+                    == Some(&local.file.sha1.as_str());
+            if unchanged {
+                report.unchanged += 1;
+            } else {
+                report.uploaded.push(format!("{}/{}", bucket, local.key));
+                uploads.push((client, bucket, *local));
+            }
+        }
+
+        let mut stale: Vec<_> = remote_sizes
+            .keys()
+            .filter(|key| key.as_str() != PACK_MANIFEST_KEY)
+            .filter(|key| is_managed(&managed, index, key))
+            .filter(|key| !bucket_files.iter().any(|local| &local.key == *key))
+            .collect();
+        stale.sort();
+        for key in stale {
+            report.deleted.push(format!("{}/{}", bucket, key));
+            deletions.push((client, bucket, key.clone()));
+        }
+    }
+
+    let mut manifest = local_manifest.unwrap_or_else(|| published.clone());
+    manifest.files = local_files.iter().map(|local| local.file.clone()).collect();
+    // Configs that were not in the tree are still published.
+    if !managed.iter().any(|folder| folder.name == "config") {
+        manifest.files.extend(
+            published
+                .files
+                .iter()
+                .filter(|file| file.path.starts_with(CONFIG_PREFIX))
+                .cloned(),
+        );
+    }
+    let manifest_changed = serde_json::to_value(&manifest)? != serde_json::to_value(&published)?;
+    if manifest_changed {
+        report
+            .uploaded
+            .push(format!("{}/{}", buckets[0], PACK_MANIFEST_KEY));
+    }
+
+    if dry_run {
+        for object in &report.uploaded {
+            log_to_frontend(&format!("[simulación] Se subiría {}", object));
+        }
+        for object in &report.deleted {
+            log_to_frontend(&format!("[simulación] Se eliminaría {}", object));
+        }
+        return Ok(report);
+    }
+
+    // Files first and the manifest after them, so it never lists a file that
+    // is not uploaded yet.
+    for (client, bucket, local) in uploads {
+        log_to_frontend(&format!("Subiendo {}/{}", bucket, local.key));
+        put_object(client, bucket, &local.key, fs::read(&local.local_path)?).await?;
+    }
+    if manifest_changed {
+        log_to_frontend(&format!("Subiendo {}/{}", buckets[0], PACK_MANIFEST_KEY));
+        put_object(
+            &clients[0],
+            &buckets[0],
+            PACK_MANIFEST_KEY,
+            serde_json::to_vec_pretty(&manifest)?,
+        )
+        .await?;
+    }
+    for (client, bucket, key) in deletions {
+        log_to_frontend(&format!("Eliminando {}/{}", bucket, key));
+        client
+            .delete_object()
+            .bucket(bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|e| InstallError::Network(e.to_string()))?;
+    }
+    log_to_frontend("Pack publicado ✅");
+    Ok(report)
+}
+
+/// Paths of the files below `directory`, relative to it and with `/`
+/// separators, sorted.
+fn list_files(directory: &Path, recursive: bool) -> Result<Vec<String>, InstallError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                files.extend(
+                    list_files(&entry.path(), true)?
+                        .into_iter()
+                        .map(|file| format!("{}/{}", name, file)),
+                );
+            } else {
+                log_to_frontend(&format!(
+                    "Se omite la carpeta {}, solo se publican archivos",
+                    entry.path().display()
+                ));
+            }
+        } else {
+            files.push(name);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `key` of bucket `index` belongs to one of the published folders.
+fn is_managed(managed: &[&Folder], index: usize, key: &str) -> bool {
+    managed.iter().any(|folder| {
+        folder.bucket == index
+            && key
+                .strip_prefix(folder.key_prefix)
+                .is_some_and(|rest| folder.recursive || !rest.contains('/'))
+    })
+}
+
+/// Size of every object in `bucket`, by key.
+async fn list_objects(client: &Client, bucket: &str) -> Result<HashMap<String, i64>, InstallError> {
+    let mut objects = HashMap::new();
+    let mut continuation_token = None;
+    loop {
+        let resp = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+            .map_err(|e| InstallError::Network(e.to_string()))?;
+        for obj in resp.contents() {
+            if let Some(key) = obj.key() {
+                objects.insert(key.to_string(), obj.size.unwrap_or(0));
+            }
+        }
+        continuation_token = resp.next_continuation_token().map(String::from);
+        if continuation_token.is_none() {
+            return Ok(objects);
+        }
+    }
+}
+
+async fn put_object(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    body: Vec<u8>,
+) -> Result<(), InstallError> {
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .body(ByteStream::from(body))
+        .send()
+        .await
+        .map_err(|e| InstallError::Network(e.to_string()))?;
+    Ok(())
+}
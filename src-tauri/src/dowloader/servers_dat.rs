@@ -0,0 +1,92 @@
+use std::{fs::File, path::Path};
+
+use nbt::{Blob, Value};
+
+use super::install_error::InstallError;
+use crate::{data_structs::PackServer, fs_util::write_atomic, log_to_frontend};
+
+/// Adds the pack's servers to `servers.dat`, or refreshes them when an entry
+/// with the same address already exists. Servers the player added themselves
+/// are never changed or reordered, even when they share a pack server's name.
+pub fn update_servers(
+    servers_dat_path: &Path,
+    pack_servers: &[PackServer],
+) -> Result<(), InstallError> {
+    if pack_servers.is_empty() {
+        return Ok(());
+    }
+
+    let mut servers = read_servers(servers_dat_path)?;
+    for pack_server in pack_servers {
+        let existing = servers.iter_mut().find_map(|server| match server {
+            Value::Compound(entry) if is_same_address(entry.get("ip"), &pack_server.ip) => {
+                Some(entry)
+            }
+            _ => None,
+        });
+        match existing {
+            Some(entry) => {
+                entry.insert("name".to_string(), Value::String(pack_server.name.clone()));
+                entry.insert("ip".to_string(), Value::String(pack_server.ip.clone()));
+                if let Some(icon) = get_server_icon(pack_server) {
+                    entry.insert("icon".to_string(), Value::String(icon));
+                }
+            }
+            None => {
+                log_to_frontend(&format!(
+                    "Añadiendo el servidor {} ({})",
+                    pack_server.name, pack_server.ip
+                ));
+                let mut entry = vec![
+                    ("name".to_string(), Value::String(pack_server.name.clone())),
+                    ("ip".to_string(), Value::String(pack_server.ip.clone())),
+                ];
+                if let Some(icon) = get_server_icon(pack_server) {
+                    entry.push(("icon".to_string(), Value::String(icon)));
+                }
+                servers.push(Value::Compound(entry.into_iter().collect()));
+            }
+        }
+    }
+
+    write_servers(servers_dat_path, servers)
+}
+
+fn read_servers(servers_dat_path: &Path) -> Result<Vec<Value>, InstallError> {
+    if !servers_dat_path.exists() {
+        return Ok(Vec::new());
+    }
+    let blob = Blob::from_reader(&mut File::open(servers_dat_path)?)
+        .map_err(|e| InstallError::InvalidData(format!("{}: {}", servers_dat_path.display(), e)))?;
+    match blob.get("servers") {
+        Some(Value::List(servers)) => Ok(servers.clone()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn write_servers(servers_dat_path: &Path, servers: Vec<Value>) -> Result<(), InstallError> {
+    let mut blob = Blob::new();
+    blob.insert("servers", Value::List(servers))
+        .map_err(|e| InstallError::InvalidData(e.to_string()))?;
+
+    let mut contents = Vec::new();
+    blob.to_writer(&mut contents)
+        .map_err(|e| InstallError::InvalidData(e.to_string()))?;
+    write_atomic(servers_dat_path, &contents)?;
+    Ok(())
+}
+
+/// Host names are case-insensitive.
+fn is_same_address(ip: Option<&Value>, expected: &str) -> bool {
+    matches!(ip, Some(Value::String(ip)) if ip.eq_ignore_ascii_case(expected))
+}
+
+/// servers.dat stores the bare base64 PNG, without the `data:` prefix.
+fn get_server_icon(pack_server: &PackServer) -> Option<String> {
+    let icon = pack_server.icon.as_deref()?.trim();
+    let icon = match icon.split_once("base64,") {
+        Some((_, data)) if icon.starts_with("data:") => data,
+        _ => icon,
+    };
+    (!icon.is_empty()).then(|| icon.to_string())
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::settings::load_settings;
+
+/// Base URLs of every metadata and download server the launcher talks to.
+/// They can be changed in the settings to use a mirror or a local mock.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Endpoints {
+    pub fabric_meta: String,
+    pub fabric_maven: String,
+    pub forge_files: String,
+    pub forge_maven: String,
+    /// Version manifest and Java runtime index.
+    pub mojang_meta: String,
+    /// Version jsons, as linked from the manifest.
+    pub mojang_piston_meta: String,
+    /// Client jars and Java runtime files.
+    pub mojang_piston_data: String,
+    pub mojang_libraries: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints {
+            fabric_meta: "https://meta2.fabricmc.net".to_string(),
+            fabric_maven: "https://maven.fabricmc.net".to_string(),
+            forge_files: "https://files.minecraftforge.net".to_string(),
+            forge_maven: "https://maven.minecraftforge.net".to_string(),
+            mojang_meta: "https://launchermeta.mojang.com".to_string(),
+            mojang_piston_meta: "https://piston-meta.mojang.com".to_string(),
+            mojang_piston_data: "https://piston-data.mojang.com".to_string(),
+            mojang_libraries: "https://libraries.minecraft.net".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// The endpoints in the current settings.
+    pub fn current() -> Self {
+        load_settings().endpoints
+    }
+
+    pub fn fabric_installers(&self) -> String {
+        join(&self.fabric_meta, "v2/versions/installer")
+    }
+
+    pub fn fabric_installer_jar(&self, version: &str) -> String {
+        join(
+            &self.fabric_maven,
+            &format!(
+                "net/fabricmc/fabric-installer/{0}/fabric-installer-{0}.jar",
+                version
+            ),
+        )
+    }
+
+    pub fn forge_promotions(&self) -> String {
+        join(
+            &self.forge_files,
+            "net/minecraftforge/forge/promotions_slim.json",
+        )
+    }
+
+    pub fn forge_metadata(&self) -> String {
+        join(
+            &self.forge_files,
+            "net/minecraftforge/forge/maven-metadata.json",
+        )
+    }
+
+    /// `full_version` is `<minecraft>-<forge>`.
+    pub fn forge_installer_jar(&self, full_version: &str) -> String {
+        join(
+            &self.forge_maven,
+            &format!(
+                "net/minecraftforge/forge/{0}/forge-{0}-installer.jar",
+                full_version
+            ),
+        )
+    }
+
+    pub fn minecraft_manifest(&self) -> String {
+        join(&self.mojang_meta, "mc/game/version_manifest.json")
+    }
+
+    pub fn java_runtimes(&self) -> String {
+        join(
+            &self.mojang_meta,
+            &format!("v1/products/java-runtime/{}/all.json", JAVA_RUNTIME_PRODUCT),
+        )
+    }
+
+    /// Base for libraries that do not say where they come from, ending in `/`.
+    pub fn libraries(&self) -> String {
+        format!("{}/", self.mojang_libraries.trim_end_matches('/'))
+    }
+
+    /// Points a URL found in downloaded metadata at the configured mirror
+    /// when it belongs to one of the default servers.
+    pub fn mirror(&self, url: &str) -> String {
+        let defaults = Endpoints::default();
+        let pairs = [
+            (&defaults.fabric_maven, &self.fabric_maven),
+            (&defaults.forge_maven, &self.forge_maven),
+            (&defaults.mojang_meta, &self.mojang_meta),
+            (&defaults.mojang_piston_meta, &self.mojang_piston_meta),
+            (&defaults.mojang_piston_data, &self.mojang_piston_data),
+            (&defaults.mojang_libraries, &self.mojang_libraries),
+        ];
+        for (default, configured) in pairs {
+            // Only whole host names, so `https://libraries.minecraft.net.evil`
+            // is left alone.
+            let path = url
+                .strip_prefix(default.as_str())
+                .filter(|path| path.is_empty() || path.starts_with('/'));
+            if let Some(path) = path {
+                return format!("{}{}", configured.trim_end_matches('/'), path);
+            }
+        }
+        url.to_string()
+    }
+}
+
+/// Id of the Java runtime product on Mojang's meta server. It is not a hash
+/// of the index, which is updated in place, but the fixed name the official
+/// launcher uses for it, so it only changes if Mojang moves the product. A
+/// mirror serves the index under the same path below `mojang_meta`.
+const JAVA_RUNTIME_PRODUCT: &str = "2ec0cc96c44e5a76b9c8b7c39df7210883d12871";
+
+fn join(base: &str, path: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), path)
+}
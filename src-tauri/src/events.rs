@@ -0,0 +1,64 @@
+use std::{
+    future::Future,
+    sync::{Arc, OnceLock},
+};
+
+/// Receives what the core logic reports while it runs, so the same code can
+/// feed the Tauri window, a terminal or a test.
+pub trait EventSink: Send + Sync {
+    fn log(&self, message: &str);
+}
+
+/// Prints every message to stderr.
+pub struct StderrSink;
+
+impl EventSink for StderrSink {
+    fn log(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+tokio::task_local! {
+    static SINK: Arc<dyn EventSink>;
+}
+
+/// Used outside `with_sink`, e.g. by background threads.
+static DEFAULT_SINK: OnceLock<Arc<dyn EventSink>> = OnceLock::new();
+
+/// Sets the sink used when no call to `with_sink` is running. Only the first
+/// call has an effect.
+pub fn set_default_sink(sink: Arc<dyn EventSink>) {
+    let _ = DEFAULT_SINK.set(sink);
+}
+
+/// Runs `future` reporting to `sink`.
+pub async fn with_sink<F: Future>(sink: Arc<dyn EventSink>, future: F) -> F::Output {
+    SINK.scope(sink, future).await
+}
+
+/// Runs `f` reporting to `sink`.
+pub fn with_sink_sync<R>(sink: Arc<dyn EventSink>, f: impl FnOnce() -> R) -> R {
+    SINK.sync_scope(sink, f)
+}
+
+/// The sink of the running call, or the default one. Work that leaves the
+/// task, like a thread or `spawn_blocking`, has to take it along and run
+/// inside `with_sink_sync`, since the sink is task-local.
+pub fn current_sink() -> Arc<dyn EventSink> {
+    SINK.try_with(Arc::clone).unwrap_or_else(|_| {
+        DEFAULT_SINK
+            .get()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(StderrSink))
+    })
+}
+
+/// Sends `message` to the sink of the running call, or the default one.
+pub fn log(message: &str) {
+    if SINK.try_with(|sink| sink.log(message)).is_err() {
+        match DEFAULT_SINK.get() {
+            Some(sink) => sink.log(message),
+            None => StderrSink.log(message),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File},
+    io::{Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Replaces `path` with `contents` through a synced temporary file next to
+/// it and a rename, so a crash mid-write leaves either the old file or the
+/// new one, never a truncated mix.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(".canada-tmp");
+    let temp_path = PathBuf::from(temp_path);
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)
+}
+
+/// Creates an empty directory below the system temp directory that no other
+/// install, launcher window or `canada-cli` run shares.
+pub fn create_temp_directory(name: &str) -> Result<PathBuf, Error> {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        let directory = env::temp_dir().join(format!(
+            "{}-{}-{}-{}",
+            name,
+            process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match fs::create_dir(&directory) {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            result => return result.map(|()| directory),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::{sync::Mutex, time::Duration};
+
+use lazy_static::lazy_static;
+use reqwest::{Client, Proxy, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{log_to_frontend, settings::load_settings};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time allowed between two reads, so big downloads are not cut short.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    /// Client shared by every request, with the proxy it was built for.
+    static ref CLIENT: Mutex<Option<(Option<String>, Client)>> = Mutex::new(None);
+}
+
+/// The shared client, rebuilt only when the proxy changes.
+fn client(proxy: Option<&str>) -> Client {
+    let proxy = proxy.map(str::to_string);
+    let mut cached = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    match cached.as_ref() {
+        Some((cached_proxy, client)) if *cached_proxy == proxy => client.clone(),
+        _ => {
+            let client = build_client(proxy.as_deref());
+            *cached = Some((proxy, client.clone()));
+            client
+        }
+    }
+}
+
+fn build_client(proxy: Option<&str>) -> Client {
+    let builder = Client::builder()
+        .user_agent(format!("CanadaLauncher/{}", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT);
+    let builder = match proxy.map(parse_proxy) {
+        Some(Ok(proxy)) => builder.proxy(proxy),
+        Some(Err(e)) => {
+            log_to_frontend(&format!("{}, se ignora", e));
+            builder
+        }
+        None => builder,
+    };
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("No se pudo crear el cliente HTTP: {}", e);
+        Client::new()
+    })
+}
+
+/// Only HTTP and HTTPS proxies are supported: reqwest is built without
+/// SOCKS, which would make every request fail.
+pub fn parse_proxy(proxy: &str) -> Result<Proxy, String> {
+    let scheme = proxy
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err(format!(
+            "Proxy inválido: {} (usa http://host:puerto o https://host:puerto)",
+            proxy
+        ));
+    }
+    Proxy::all(proxy).map_err(|e| format!("Proxy inválido: {}", e))
+}
+
+/// GETs `url`, or its mirror when one is configured, retrying timeouts,
+/// connection failures and server errors with exponential backoff. Error
+/// statuses are returned as errors.
+pub async fn get(url: &str) -> Result<Response, reqwest::Error> {
+    let settings = load_settings();
+    let url = settings.endpoints.mirror(url);
+    let client = client(settings.proxy());
+    let mut attempt = 1;
+    loop {
+        let result = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(Response::error_for_status);
+        match result {
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e) => {
+                tokio::time::sleep(INITIAL_BACKOFF * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+pub async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, reqwest::Error> {
+    get(url).await?.json().await
+}
+
+pub async fn get_bytes(url: &str) -> Result<Vec<u8>, reqwest::Error> {
+    Ok(get(url).await?.bytes().await?.to_vec())
+}
+
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout()
+        || e.is_connect()
+        || e.status().is_some_and(|status| {
+            status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+        })
+}
@@ -0,0 +1,233 @@
+//! Sync, install and profile operations shared by the Tauri commands and
+//! `canada-cli`. Every call reports its progress to the given `EventSink`.
+//!
+//! The sink is task-local: work that leaves the calling task, like a thread
+//! or `spawn_blocking`, reports to the default sink unless it takes
+//! `events::current_sink()` along. Watching the game after launch is left to
+//! `minecraft_launcher::launch`, which needs the Tauri `AppHandle` for the
+//! tray and the `minecraft-exited` event.
+
+use std::{collections::HashMap, io, path::Path, sync::Arc};
+
+use crate::{
+    api_error::ApiError,
+    cache,
+    data_structs::{
+        self, ForgeVersions, MinecraftApiResponse, ModLoaders, PackManifest, VersionType,
+    },
+    dowloader::{self, InstallError, InstanceOptions, PublishReport},
+    events::{self, EventSink},
+    log_to_frontend, minecraft_launcher,
+    settings::{load_settings, Settings},
+};
+
+pub fn parse_loader(name: &str) -> Result<ModLoaders, InstallError> {
+    match name {
+        "forge" => Ok(ModLoaders::Forge),
+        "fabric" => Ok(ModLoaders::Fabric),
+        "vanilla" => Ok(ModLoaders::Vanilla),
+        _ => Err(InstallError::InvalidData(format!(
+            "Tipo de mod inválido: {}",
+            name
+        ))),
+    }
+}
+
+/// Install options of the selected instance.
+pub fn instance_options(settings: &Settings) -> InstanceOptions {
+    let instance = settings.instance();
+    InstanceOptions {
+        java_args: instance.memory.map(|memory| memory.to_java_args()),
+        java: instance.java,
+        resolution: instance.resolution,
+        offline: settings.offline_mode,
+    }
+}
+
+/// Syncs the pack and installs `minecraft_version` with the loader into the
+/// selected instance, using its saved settings.
+pub async fn install(
+    sink: Arc<dyn EventSink>,
+    minecraft_version: String,
+    loader: ModLoaders,
+    loader_version: String,
+) -> Result<(), InstallError> {
+    events::with_sink(sink, async move {
+        log_to_frontend("Iniciando el proceso de descarga... 📥");
+        log_to_frontend(&format!(
+            "Versión de Minecraft: {}, Mod loader: {:?}, Versión de Mod: {}",
+            minecraft_version, loader, loader_version
+        ));
+
+        let settings = load_settings();
+        let options = instance_options(&settings);
+        if let Some(java_args) = &options.java_args {
+            log_to_frontend(&format!("Argumentos de Java: {}", java_args));
+        }
+
+        dowloader::start_install(
+            &settings.selected_instance,
+            loader,
+            loader_version,
+            minecraft_version,
+            options,
+        )
+        .await
+        .inspect_err(|e| {
+            log_to_frontend(&format!("❌ La instalación falló: {}", e));
+            if matches!(e, InstallError::Network(_)) && !settings.offline_mode {
+                log_to_frontend(
+                    "Si no tienes conexión, activa el modo sin conexión para jugar con lo ya instalado.",
+                );
+            }
+        })
+    })
+    .await
+}
+
+/// Syncs the mods and resource packs buckets into the selected instance.
+pub async fn sync_pack(sink: Arc<dyn EventSink>) -> Result<PackManifest, InstallError> {
+    let instance_id = load_settings().selected_instance;
+    events::with_sink(sink, dowloader::sync_pack(&instance_id)).await
+}
+
+/// Installs `minecraft_version` with the loader, without syncing the pack or
+/// touching the launcher profile.
+pub async fn install_loader(
+    sink: Arc<dyn EventSink>,
+    minecraft_version: String,
+    loader: ModLoaders,
+    loader_version: String,
+) -> Result<(), InstallError> {
+    events::with_sink(sink, async move {
+        dowloader::get_loader_installer(loader, loader_version, minecraft_version)
+            .install_loader()
+            .await
+    })
+    .await
+}
+
+/// Writes the profile of the selected instance for an installed version,
+/// applying the pack manifest of the last sync.
+pub async fn create_profile(
+    sink: Arc<dyn EventSink>,
+    minecraft_version: String,
+    loader: ModLoaders,
+    loader_version: String,
+) -> Result<(), InstallError> {
+    events::with_sink(sink, async move {
+        let loader_installer =
+            dowloader::get_loader_installer(loader, loader_version, minecraft_version);
+        if !loader_installer.is_version_installed() {
+            return Err(InstallError::InvalidData(format!(
+                "{} no está instalado",
+                loader_installer.get_version_format()
+            )));
+        }
+        let settings = load_settings();
+        dowloader::create_profile(
+            &settings.selected_instance,
+            &loader_installer,
+            instance_options(&settings),
+            dowloader::get_cached_pack_manifest(),
+        )
+        .await
+    })
+    .await
+}
+
+/// Publishes the pack tree at `root` to the buckets. See
+/// `dowloader::publish_pack`.
+pub async fn publish_pack(
+    sink: Arc<dyn EventSink>,
+    root: &Path,
+    dry_run: bool,
+) -> Result<PublishReport, InstallError> {
+    events::with_sink(sink, dowloader::publish_pack(root, dry_run)).await
+}
+
+/// Opens the Minecraft Launcher, at the path saved in the settings if any.
+pub fn launch(sink: Arc<dyn EventSink>) -> Result<(), io::Error> {
+    events::with_sink_sync(sink, || {
+        let settings = load_settings();
+        minecraft_launcher::open(settings.launcher_path().map(Path::new)).map(|_| ())
+    })
+}
+
+pub fn remove_profile(sink: Arc<dyn EventSink>, instance_id: &str) -> Result<bool, InstallError> {
+    events::with_sink_sync(sink, || dowloader::remove_instance_profile(instance_id))
+}
+
+pub fn list_profile_backups(sink: Arc<dyn EventSink>) -> Result<Vec<String>, InstallError> {
+    events::with_sink_sync(sink, dowloader::list_profile_backups)
+}
+
+pub fn restore_profile_backup(
+    sink: Arc<dyn EventSink>,
+    backup_name: Option<&str>,
+) -> Result<String, InstallError> {
+    events::with_sink_sync(sink, || dowloader::restore_profile_backup(backup_name))
+}
+
+pub async fn fabric_versions(sink: Arc<dyn EventSink>) -> Result<Vec<String>, ApiError> {
+    events::with_sink(sink, async {
+        log_to_frontend("Obteniendo versiones de Fabric... 📡");
+        cache::get_or_fetch(
+            "fabric_versions",
+            cache::FABRIC_VERSIONS_TTL,
+            is_offline(),
+            data_structs::get_fabric_versions,
+        )
+        .await
+        .inspect(|_| log_to_frontend("Versiones de Fabric obtenidas exitosamente! ✅"))
+        .inspect_err(|e| {
+            log_to_frontend(&format!("Error al obtener las versiones de Fabric: {}", e))
+        })
+    })
+    .await
+}
+
+pub async fn forge_versions(
+    sink: Arc<dyn EventSink>,
+) -> Result<HashMap<String, ForgeVersions>, ApiError> {
+    events::with_sink(sink, async {
+        log_to_frontend("Obteniendo versiones de Forge... 📡");
+        cache::get_or_fetch(
+            "forge_versions",
+            cache::FORGE_VERSIONS_TTL,
+            is_offline(),
+            data_structs::get_forge_versions,
+        )
+        .await
+        .inspect(|_| log_to_frontend("Versiones de Forge obtenidas exitosamente! ✅"))
+        .inspect_err(|e| {
+            log_to_frontend(&format!("Error al obtener las versiones de Forge: {}", e))
+        })
+    })
+    .await
+}
+
+/// The Mojang manifest with only `version_types`, or every version.
+pub async fn minecraft_manifest(
+    sink: Arc<dyn EventSink>,
+    version_types: Option<Vec<VersionType>>,
+) -> Result<MinecraftApiResponse, ApiError> {
+    events::with_sink(sink, async move {
+        let manifest = cache::get_or_fetch(
+            "minecraft_manifest",
+            cache::MINECRAFT_MANIFEST_TTL,
+            is_offline(),
+            data_structs::get_minecraft_manifest,
+        )
+        .await?;
+        Ok(data_structs::filter_minecraft_manifest(
+            manifest,
+            version_types.as_deref(),
+        ))
+    })
+    .await
+}
+
+fn is_offline() -> bool {
+    load_settings().offline_mode
+}
@@ -1,12 +1,29 @@
-use data_structs::ModLoaders;
-use json_struct_db::JsonConverter;
+use api_error::ApiError;
+use data_structs::{
+    ForgeVersions, JavaSelection, MinecraftApiResponse, ProfileResolution, VersionType,
+};
+use dowloader::InstallError;
+use events::EventSink;
+use memory_settings::{MemoryInfo, MemorySettings};
+use minecraft_launcher::{MinecraftLauncher, PostLaunchMode};
 use self_update::cargo_crate_version;
-use serde::Deserialize;
+use settings::{load_settings, store_settings, Settings, Theme, UpdateChannel};
 use std::collections::HashMap;
-mod data_structs;
-mod dowloader;
+use std::path::PathBuf;
+use std::sync::Arc;
+pub mod api_error;
+mod cache;
+pub mod data_structs;
+pub mod dowloader;
+pub mod endpoints;
+pub mod events;
+mod fs_util;
+mod http;
+pub mod launcher_core;
+mod memory_settings;
+mod minecraft_launcher;
+pub mod settings;
 
-use std::sync::OnceLock;
 use tauri::Emitter;
 
 use tauri::AppHandle;
@@ -18,79 +35,68 @@ struct LogPayload {
     message: String,
 }
 
-static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+/// Sends the core's messages to the window as `log-event`s.
+struct TauriSink(AppHandle);
 
-pub fn set_app_handle(app_handle: AppHandle) {
-    APP_HANDLE.set(app_handle).expect("AppHandle already set");
+impl EventSink for TauriSink {
+    fn log(&self, message: &str) {
+        let _ = self.0.emit(
+            "log-event",
+            LogPayload {
+                message: message.to_string(),
+            },
+        );
+    }
+}
+
+fn tauri_sink(app: &AppHandle) -> Arc<dyn EventSink> {
+    Arc::new(TauriSink(app.clone()))
 }
 
+/// Reports `message` to whoever is driving the current operation.
 pub fn log_to_frontend(message: &str) {
-    if let Some(app_handle) = APP_HANDLE.get() {
-        app_handle
-            .emit(
-                "log-event",
-                LogPayload {
-                    message: message.to_string(),
-                },
-            )
-            .unwrap();
-    } else {
-        eprintln!("AppHandle no está configurado.");
-    }
+    events::log(message);
 }
-#[derive(Serialize, Deserialize)]
-struct Data {
+
+#[tauri::command]
+fn save_data(
     minecraft_version: String,
     mod_loader: String,
     mod_loader_version: String,
+) -> Result<(), String> {
+    let mut settings = load_settings();
+    let instance = settings.instance_mut();
+    instance.minecraft_version = minecraft_version;
+    instance.mod_loader = mod_loader;
+    instance.mod_loader_version = mod_loader_version;
+    save_settings(&settings)
 }
 
-impl Data {
-    fn new() -> Self {
-        Data {
-            minecraft_version: String::from(""),
-            mod_loader: String::from(""),
-            mod_loader_version: String::from(""),
-        }
-    }
+#[tauri::command]
+fn get_settings() -> Settings {
+    load_settings()
 }
 
-impl json_struct_db::JsonConverter for Data {
-    fn to_json(&self) -> String {
-        serde_json::to_string(self).expect("Failed to serialize MyData")
-    }
-
-    fn from_json(json: String) -> Self {
-        serde_json::from_str(&json).expect("Failed to deserialize MyData")
-    }
+#[tauri::command]
+fn get_settings_warning() -> Option<String> {
+    settings::take_settings_warning()
 }
 
 #[tauri::command]
-fn save_data(minecraft_version: String, mod_loader: String, mod_loader_version: String) {
-    let data: Data = Data {
-        minecraft_version: minecraft_version,
-        mod_loader: mod_loader,
-        mod_loader_version: mod_loader_version,
-    };
-    match json_struct_db::save(data, "CanadaLauncher") {
-        Ok(path) => {
-            println!("Data saved to {}", path)
-        }
-        Err(e) => {
-            println!("{}", e)
-        }
-    };
+fn set_preferences(language: String, channel: UpdateChannel, theme: Theme) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.language = language;
+    settings.channel = channel;
+    settings.theme = theme;
+    save_settings(&settings)
 }
 
-#[tauri::command]
-fn get_data() -> Data {
-    match json_struct_db::read("CanadaLauncher") {
-        Ok(data) => return Data::from_json(data),
-        Err(e) => {
-            println!("{}", e.message);
-            return Data::new();
-        }
-    }
+fn save_settings(settings: &Settings) -> Result<(), String> {
+    store_settings(settings).map_err(|e| {
+        let message = format!("No se pudieron guardar los ajustes: {}", e);
+        log_to_frontend(&message);
+        message
+    })
 }
 
 #[tauri::command]
@@ -99,72 +105,182 @@ fn get_version() -> String {
 }
 
 #[tauri::command]
-async fn get_fabric_versions() -> Vec<String> {
-    log_to_frontend("Obteniendo versiones de Fabric... 📡");
-    match data_structs::get_fabric_versions().await {
-        Err(e) => {
-            log_to_frontend(&format!("Error al obtener las versiones de Fabric: {}", e));
-            vec![format!("Error cargando versiones {}", e)]
-        }
-        Ok(version) => {
-            log_to_frontend("Versiones de Fabric obtenidas exitosamente! ✅");
-            version
-        }
-    }
+async fn get_fabric_versions(app: AppHandle) -> Result<Vec<String>, ApiError> {
+    launcher_core::fabric_versions(tauri_sink(&app)).await
 }
 
 #[tauri::command]
-async fn get_forge_versions() -> HashMap<String, String> {
-    log_to_frontend("Obteniendo versiones de Forge... 📡");
-    match data_structs::get_forge_versions().await {
-        Err(e) => {
-            log_to_frontend(&format!("Error al obtener las versiones de Forge: {}", e));
-            let mut error_map = HashMap::new();
-            error_map.insert(
-                "error".to_string(),
-                format!("Error cargando versiones: {}", e),
-            );
-            error_map
-        }
-        Ok(version) => {
-            log_to_frontend("Versiones de Forge obtenidas exitosamente! ✅");
-            version
-        }
-    }
+async fn get_forge_versions(app: AppHandle) -> Result<HashMap<String, ForgeVersions>, ApiError> {
+    launcher_core::forge_versions(tauri_sink(&app)).await
+}
+
+#[tauri::command]
+async fn start_downloading(
+    app: AppHandle,
+    minecraft_version: String,
+    mod_type_str: String,
+    mod_version: String,
+    post_launch: Option<PostLaunchMode>,
+) -> Result<(), InstallError> {
+    let loader = launcher_core::parse_loader(&mod_type_str)?;
+    launcher_core::install(tauri_sink(&app), minecraft_version, loader, mod_version).await?;
+
+    log_to_frontend("Proceso de descarga completado. Iniciando Minecraft... 🚀");
+    minecraft_launcher::launch(
+        &app,
+        post_launch.unwrap_or_default(),
+        get_launcher_override().as_deref(),
+    )
+    .map_err(|e| {
+        log_to_frontend(&format!("❌ No se pudo iniciar Minecraft: {}", e));
+        InstallError::Launcher(e.to_string())
+    })
+}
+
+#[tauri::command]
+fn remove_profile(app: AppHandle, instance_id: Option<String>) -> Result<bool, InstallError> {
+    launcher_core::remove_profile(
+        tauri_sink(&app),
+        instance_id
+            .as_deref()
+            .unwrap_or(dowloader::DEFAULT_INSTANCE_ID),
+    )
+}
+
+#[tauri::command]
+fn list_profile_backups(app: AppHandle) -> Result<Vec<String>, InstallError> {
+    launcher_core::list_profile_backups(tauri_sink(&app))
 }
 
 #[tauri::command]
-async fn start_downloading(minecraft_version: String, mod_type_str: String, mod_version: String) {
-    log_to_frontend("Iniciando el proceso de descarga... 📥");
-    log_to_frontend(&format!(
-        "Versión de Minecraft: {}, Tipo de Mod: {}, Versión de Mod: {}",
-        minecraft_version, mod_type_str, mod_version
-    ));
+fn restore_profile_backup(
+    app: AppHandle,
+    backup_name: Option<String>,
+) -> Result<String, InstallError> {
+    launcher_core::restore_profile_backup(tauri_sink(&app), backup_name.as_deref())
+}
+
+#[tauri::command]
+fn relaunch_minecraft(app: AppHandle, post_launch: Option<PostLaunchMode>) -> Result<(), String> {
+    log_to_frontend("Reiniciando Minecraft... 🚀");
+    minecraft_launcher::launch(
+        &app,
+        post_launch.unwrap_or_default(),
+        get_launcher_override().as_deref(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn get_launcher_override() -> Option<PathBuf> {
+    load_settings().launcher_path().map(PathBuf::from)
+}
+
+#[tauri::command]
+fn get_minecraft_launcher() -> Result<MinecraftLauncher, String> {
+    minecraft_launcher::find_minecraft_launcher(get_launcher_override().as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_minecraft_launcher_path(path: Option<String>) -> Result<MinecraftLauncher, String> {
+    let mut settings = load_settings();
+    settings.launcher_path = path.filter(|path| !path.trim().is_empty());
+    save_settings(&settings)?;
+    get_minecraft_launcher()
+}
+
+#[tauri::command]
+fn open_crash_report(path: String) -> Result<(), String> {
+    minecraft_launcher::open_crash_report(std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_memory_info() -> MemoryInfo {
+    memory_settings::get_memory_info()
+}
+
+#[tauri::command]
+fn get_memory_settings() -> MemorySettings {
+    load_settings().instance().memory.unwrap_or_default()
+}
 
-    let loader = match mod_type_str.as_str() {
-        "forge" => ModLoaders::Forge,
-        "fabric" => ModLoaders::Fabric,
-        _ => {
-            eprintln!("Tipo de mod inválido: {}", mod_type_str);
-            return;
+/// Saves the memory settings, returning the resulting `javaArgs`. With
+/// `memory` empty installs leave the profile's `javaArgs` alone, and only a
+/// new profile gets the recommended ones returned here.
+#[tauri::command]
+fn set_memory_settings(memory: Option<MemorySettings>) -> Result<String, String> {
+    if let Some(memory) = &memory {
+        memory.validate()?;
+    }
+    let java_args = memory.clone().unwrap_or_default().to_java_args();
+    let mut settings = load_settings();
+    settings.instance_mut().memory = memory;
+    save_settings(&settings)?;
+    Ok(java_args)
+}
+
+#[tauri::command]
+fn set_profile_settings(
+    resolution: Option<ProfileResolution>,
+    java: JavaSelection,
+) -> Result<(), String> {
+    if resolution.is_some_and(|resolution| resolution.width == 0 || resolution.height == 0) {
+        return Err("La resolución debe ser mayor que 0".to_string());
+    }
+    if let JavaSelection::Custom(path) = &java {
+        if !std::path::Path::new(path).is_file() {
+            return Err(format!("No se encontró Java en {}", path));
         }
-    };
+    }
+    let mut settings = load_settings();
+    let instance = settings.instance_mut();
+    instance.resolution = resolution;
+    instance.java = java;
+    save_settings(&settings)
+}
 
-    log_to_frontend(&format!("Usando el cargador de mods: {:?}", loader));
+#[tauri::command]
+async fn get_minecraft_versions(app: AppHandle) -> Result<Vec<String>, ApiError> {
+    let manifest =
+        launcher_core::minecraft_manifest(tauri_sink(&app), Some(vec![VersionType::Release]))
+            .await?;
+    Ok(manifest
+        .versions
+        .into_iter()
+        .map(|version| version.id)
+        .collect())
+}
 
-    let _ = dowloader::start_install(loader, mod_version, minecraft_version).await;
+#[tauri::command]
+async fn get_minecraft_manifest(
+    app: AppHandle,
+    version_types: Option<Vec<VersionType>>,
+) -> Result<MinecraftApiResponse, ApiError> {
+    launcher_core::minecraft_manifest(tauri_sink(&app), version_types).await
+}
 
-    log_to_frontend("Proceso de descarga completado. Iniciando Minecraft... 🚀");
-    open_minecraft_launcher();
-    std::process::exit(0);
+#[tauri::command]
+fn set_offline_mode(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.offline_mode = enabled;
+    save_settings(&settings)?;
+    log_to_frontend(if enabled {
+        "Modo sin conexión activado."
+    } else {
+        "Modo sin conexión desactivado."
+    });
+    Ok(())
 }
 
 #[tauri::command]
-async fn get_minecraft_versions() -> Vec<String> {
-    match data_structs::get_minecraft_versions().await {
-        Err(e) => vec![format!("Error cargando versiones {}", e)],
-        Ok(version) => version,
+fn set_proxy(proxy: Option<String>) -> Result<(), String> {
+    let proxy = proxy.filter(|proxy| !proxy.trim().is_empty());
+    if let Some(proxy) = &proxy {
+        http::parse_proxy(proxy)?;
     }
+    let mut settings = load_settings();
+    settings.proxy = proxy;
+    save_settings(&settings)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -172,7 +288,7 @@ pub fn run() {
     let _ = update_exe();
     tauri::Builder::default()
         .setup(|app| {
-            set_app_handle(app.handle().clone());
+            events::set_default_sink(tauri_sink(app.handle()));
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
@@ -181,9 +297,25 @@ pub fn run() {
             get_fabric_versions,
             get_forge_versions,
             get_minecraft_versions,
+            get_minecraft_manifest,
             start_downloading,
+            relaunch_minecraft,
+            remove_profile,
+            list_profile_backups,
+            restore_profile_backup,
+            open_crash_report,
+            get_minecraft_launcher,
+            set_minecraft_launcher_path,
+            get_memory_info,
+            get_memory_settings,
+            set_memory_settings,
+            set_profile_settings,
             save_data,
-            get_data
+            get_settings,
+            get_settings_warning,
+            set_offline_mode,
+            set_preferences,
+            set_proxy
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -222,14 +354,3 @@ fn update_exe() -> Result<(), Box<dyn (::std::error::Error)>> {
     }
     Ok(())
 }
-
-fn open_minecraft_launcher() {
-    let minecraft_launcher_path = if cfg!(target_os = "windows") {
-        "C:/XboxGames/Minecraft Launcher/Content/Minecraft.exe"
-    } else {
-        "/Applications/Minecraft.app/Contents/MacOS/launcher"
-    };
-
-    log_to_frontend(&format!("Iniciando Minecraft: {}", minecraft_launcher_path));
-    let _ = std::process::Command::new(minecraft_launcher_path).spawn();
-}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use sysinfo::System;
+
+const MIN_HEAP_MB: u64 = 1024;
+const MAX_RECOMMENDED_MB: u64 = 8192;
+/// Memory left for the OS and the launcher when suggesting a heap size.
+const SYSTEM_RESERVED_MB: u64 = 2048;
+
+/// Aikar's G1 flags, tuned for large modded heaps.
+const AIKAR_FLAGS: &str = "-XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200 \
+-XX:+UnlockExperimentalVMOptions -XX:+DisableExplicitGC -XX:+AlwaysPreTouch \
+-XX:G1NewSizePercent=30 -XX:G1MaxNewSizePercent=40 -XX:G1HeapRegionSize=8M \
+-XX:G1ReservePercent=20 -XX:G1HeapWastePercent=5 -XX:G1MixedGCCountTarget=4 \
+-XX:InitiatingHeapOccupancyPercent=15 -XX:G1MixedGCLiveThresholdPercent=90 \
+-XX:G1RSetUpdatingPauseIntervalMillis=100 -XX:SurvivorRatio=32 -XX:+PerfDisableSharedMem \
+-XX:MaxTenuringThreshold=1";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmPreset {
+    #[default]
+    None,
+    Aikar,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MemorySettings {
+    #[serde(default)]
+    pub min_memory_mb: Option<u64>,
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: u64,
+    #[serde(default)]
+    pub preset: JvmPreset,
+    #[serde(default)]
+    pub extra_args: String,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        MemorySettings {
+            min_memory_mb: None,
+            max_memory_mb: get_recommended_memory_mb(get_total_memory_mb()),
+            preset: JvmPreset::None,
+            extra_args: String::new(),
+        }
+    }
+}
+
+fn default_max_memory_mb() -> u64 {
+    get_recommended_memory_mb(get_total_memory_mb())
+}
+
+/// Reads saved memory settings, dropping them when they are malformed so a
+/// bad value never makes the rest of the settings unreadable.
+pub fn deserialize_saved<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<MemorySettings>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value)
+        .inspect_err(|e| eprintln!("Ajustes de memoria inválidos, se ignoran: {}", e))
+        .unwrap_or_default())
+}
+
+impl MemorySettings {
+    /// Checks the heap sizes against each other and the installed memory.
+    pub fn validate(&self) -> Result<(), String> {
+        let total_memory_mb = get_total_memory_mb();
+        if self.max_memory_mb < MIN_HEAP_MB {
+            return Err(format!(
+                "La memoria máxima debe ser de al menos {} MB",
+                MIN_HEAP_MB
+            ));
+        }
+        if total_memory_mb > 0 && self.max_memory_mb > total_memory_mb {
+            return Err(format!(
+                "La memoria máxima ({} MB) supera la memoria del sistema ({} MB)",
+                self.max_memory_mb, total_memory_mb
+            ));
+        }
+        if let Some(min_memory_mb) = self.min_memory_mb {
+            if min_memory_mb > self.max_memory_mb {
+                return Err(format!(
+                    "La memoria mínima ({} MB) supera la máxima ({} MB)",
+                    min_memory_mb, self.max_memory_mb
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the `javaArgs` of the launcher profile.
+    pub fn to_java_args(&self) -> String {
+        let mut args = vec![format!("-Xmx{}M", self.max_memory_mb)];
+        match (self.min_memory_mb, self.preset) {
+            (Some(min_memory_mb), _) => args.push(format!("-Xms{}M", min_memory_mb)),
+            // Aikar's flags pre-touch the heap, which expects -Xms equal to -Xmx.
+            (None, JvmPreset::Aikar) => args.push(format!("-Xms{}M", self.max_memory_mb)),
+            (None, JvmPreset::None) => {}
+        }
+        if self.preset == JvmPreset::Aikar {
+            args.push(AIKAR_FLAGS.to_string());
+        }
+        let extra_args = self.extra_args.trim();
+        if !extra_args.is_empty() {
+            args.push(extra_args.to_string());
+        }
+        args.join(" ")
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryInfo {
+    pub total_memory_mb: u64,
+    pub recommended_memory_mb: u64,
+}
+
+pub fn get_memory_info() -> MemoryInfo {
+    let total_memory_mb = get_total_memory_mb();
+    MemoryInfo {
+        total_memory_mb,
+        recommended_memory_mb: get_recommended_memory_mb(total_memory_mb),
+    }
+}
+
+/// Installed memory in MB, or 0 when the platform does not report it.
+pub fn get_total_memory_mb() -> u64 {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.total_memory() / 1024 / 1024
+}
+
+/// Half of the installed memory, never less than 2 GB and never more than
+/// 8 GB, leaving room for the system on small machines.
+pub fn get_recommended_memory_mb(total_memory_mb: u64) -> u64 {
+    if total_memory_mb == 0 {
+        return 4096;
+    }
+    let half = total_memory_mb / 2;
+    let available = total_memory_mb.saturating_sub(SYSTEM_RESERVED_MB);
+    let recommended = half.max(2048).min(available.max(MIN_HEAP_MB));
+    // Round down to a multiple of 512 MB.
+    (recommended.min(MAX_RECOMMENDED_MB) / 512 * 512).max(MIN_HEAP_MB)
+}
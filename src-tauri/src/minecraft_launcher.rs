@@ -0,0 +1,337 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    thread,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager,
+};
+
+use crate::{dowloader::get_instance_directory, events, log_to_frontend, settings::load_settings};
+
+const TRAY_ID: &str = "canada-tray";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostLaunchMode {
+    #[default]
+    StayOpen,
+    MinimizeToTray,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LauncherSource {
+    UserOverride,
+    XboxApp,
+    MicrosoftStore,
+    LegacyInstall,
+    MacApplication,
+    Flatpak,
+    Path,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MinecraftLauncher {
+    pub source: LauncherSource,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl MinecraftLauncher {
+    fn new(source: LauncherSource, program: PathBuf) -> Self {
+        MinecraftLauncher {
+            source,
+            program,
+            args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct LauncherClosedPayload {
+    /// Exit code of the Minecraft Launcher, not of the game.
+    exit_code: Option<i32>,
+    crash_report: Option<String>,
+}
+
+/// Opens the Minecraft Launcher and watches it on a background thread. The
+/// game is started by the launcher, not by us, so only the launcher's exit
+/// is seen: when it closes a `minecraft-launcher-closed` event is sent with
+/// its exit code and the instance's newest crash report written since
+/// launch, if any.
+pub fn launch(
+    app: &AppHandle,
+    mode: PostLaunchMode,
+    launcher_override: Option<&Path>,
+) -> Result<(), Error> {
+    let launcher = find_minecraft_launcher(launcher_override)?;
+    let child = open_minecraft_launcher(&launcher)?;
+    let launched_at = SystemTime::now();
+
+    // Store installs are started through the shell, which exits right away.
+    if launcher.source == LauncherSource::MicrosoftStore {
+        return Ok(());
+    }
+
+    if mode == PostLaunchMode::MinimizeToTray {
+        hide_to_tray(app);
+    }
+
+    // The sink is task-local, so the watcher takes the caller's along.
+    let app = app.clone();
+    let sink = events::current_sink();
+    thread::spawn(move || events::with_sink_sync(sink, || watch(app, child, launched_at)));
+    Ok(())
+}
+
+/// Opens the Minecraft Launcher without watching it.
+pub fn open(launcher_override: Option<&Path>) -> Result<MinecraftLauncher, Error> {
+    let launcher = find_minecraft_launcher(launcher_override)?;
+    open_minecraft_launcher(&launcher)?;
+    Ok(launcher)
+}
+
+fn open_minecraft_launcher(launcher: &MinecraftLauncher) -> Result<Child, Error> {
+    log_to_frontend(&format!(
+        "Iniciando Minecraft: {} {}",
+        launcher.program.display(),
+        launcher.args.join(" ")
+    ));
+    Command::new(&launcher.program)
+        .args(&launcher.args)
+        .spawn()
+        .map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("No se pudo abrir {}: {}", launcher.program.display(), e),
+            )
+        })
+}
+
+/// Looks for the official Minecraft Launcher, trying the path saved in the
+/// settings first and then the usual install locations of each platform.
+pub fn find_minecraft_launcher(
+    launcher_override: Option<&Path>,
+) -> Result<MinecraftLauncher, Error> {
+    if let Some(path) = launcher_override {
+        if path.is_file() {
+            return Ok(MinecraftLauncher::new(
+                LauncherSource::UserOverride,
+                path.to_path_buf(),
+            ));
+        }
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "La ruta configurada del Minecraft Launcher no existe: {}",
+                path.display()
+            ),
+        ));
+    }
+
+    detect_platform_launcher().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "No se encontró el Minecraft Launcher. Instálalo o configura su ruta en los ajustes.",
+        )
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn detect_platform_launcher() -> Option<MinecraftLauncher> {
+    const STORE_PACKAGE: &str = "Microsoft.4297127D64EC6_8wekyb3d8bbwe";
+
+    let xbox_app = ('C'..='Z')
+        .map(|drive| {
+            PathBuf::from(format!(
+                "{}:/XboxGames/Minecraft Launcher/Content/Minecraft.exe",
+                drive
+            ))
+        })
+        .find(|path| path.is_file());
+    if let Some(path) = xbox_app {
+        return Some(MinecraftLauncher::new(LauncherSource::XboxApp, path));
+    }
+
+    if let Some(local_app_data) = dirs::data_local_dir() {
+        if local_app_data.join("Packages").join(STORE_PACKAGE).is_dir() {
+            return Some(MinecraftLauncher {
+                source: LauncherSource::MicrosoftStore,
+                program: PathBuf::from("explorer.exe"),
+                args: vec![format!("shell:AppsFolder\\{}!Minecraft", STORE_PACKAGE)],
+            });
+        }
+    }
+
+    ["ProgramFiles(x86)", "ProgramFiles"]
+        .iter()
+        .filter_map(|variable| std::env::var_os(variable).map(PathBuf::from))
+        .flat_map(|program_files| {
+            [
+                program_files
+                    .join("Minecraft Launcher")
+                    .join("MinecraftLauncher.exe"),
+                program_files
+                    .join("Minecraft")
+                    .join("MinecraftLauncher.exe"),
+            ]
+        })
+        .find(|path| path.is_file())
+        .map(|path| MinecraftLauncher::new(LauncherSource::LegacyInstall, path))
+}
+
+#[cfg(target_os = "macos")]
+fn detect_platform_launcher() -> Option<MinecraftLauncher> {
+    let application = Path::new("Applications/Minecraft.app/Contents/MacOS/launcher");
+    std::iter::once(Path::new("/").join(application))
+        .chain(dirs::home_dir().map(|home| home.join(application)))
+        .find(|path| path.is_file())
+        .map(|path| MinecraftLauncher::new(LauncherSource::MacApplication, path))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn detect_platform_launcher() -> Option<MinecraftLauncher> {
+    const FLATPAK_APP: &str = "com.mojang.Minecraft";
+
+    let flatpak_installed = std::iter::once(PathBuf::from("/var/lib/flatpak/app"))
+        .chain(dirs::data_dir().map(|data| data.join("flatpak").join("app")))
+        .any(|apps| apps.join(FLATPAK_APP).is_dir());
+    if flatpak_installed {
+        if let Some(flatpak) = find_in_path("flatpak") {
+            return Some(MinecraftLauncher {
+                source: LauncherSource::Flatpak,
+                program: flatpak,
+                args: vec!["run".to_string(), FLATPAK_APP.to_string()],
+            });
+        }
+    }
+
+    find_in_path("minecraft-launcher")
+        .map(|path| MinecraftLauncher::new(LauncherSource::Path, path))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|directory| directory.join(program))
+        .find(|path| path.is_file())
+}
+
+fn watch(app: AppHandle, mut child: Child, launched_at: SystemTime) {
+    let exit_code = match child.wait() {
+        Ok(status) => status.code(),
+        Err(e) => {
+            log_to_frontend(&format!(
+                "No se pudo seguir el proceso del Minecraft Launcher: {}",
+                e
+            ));
+            None
+        }
+    };
+    let crash_report = find_crash_report(launched_at);
+
+    match exit_code {
+        Some(code) if code != 0 => log_to_frontend(&format!(
+            "❌ El Minecraft Launcher terminó con código {}",
+            code
+        )),
+        _ => log_to_frontend("El Minecraft Launcher se cerró."),
+    }
+    if let Some(report) = &crash_report {
+        log_to_frontend(&format!(
+            "❌ Minecraft dejó un informe de error: {}",
+            report.display()
+        ));
+    }
+
+    show_window(&app);
+    let _ = app.emit(
+        "minecraft-launcher-closed",
+        LauncherClosedPayload {
+            exit_code,
+            crash_report: crash_report.map(|report| report.to_string_lossy().to_string()),
+        },
+    );
+}
+
+fn get_crash_reports_directory() -> PathBuf {
+    get_instance_directory(&load_settings().selected_instance).join("crash-reports")
+}
+
+fn find_crash_report(since: SystemTime) -> Option<PathBuf> {
+    fs::read_dir(get_crash_reports_directory())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            (modified >= since).then(|| (modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+pub fn open_crash_report(path: &Path) -> Result<(), Error> {
+    let crash_reports_directory = get_crash_reports_directory().canonicalize()?;
+    let path = path.canonicalize()?;
+    if !path.starts_with(&crash_reports_directory) {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "Solo se pueden abrir informes de error de Minecraft",
+        ));
+    }
+
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn()?;
+    Ok(())
+}
+
+fn hide_to_tray(app: &AppHandle) {
+    if app.tray_by_id(TRAY_ID).is_none() {
+        let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+            .tooltip("Canada Launcher")
+            .on_tray_icon_event(|tray, event| {
+                if let TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } = event
+                {
+                    show_window(tray.app_handle());
+                }
+            });
+        if let Some(icon) = app.default_window_icon() {
+            tray = tray.icon(icon.clone());
+        }
+        if let Err(e) = tray.build(app) {
+            log_to_frontend(&format!("No se pudo crear el icono de la bandeja: {}", e));
+            return;
+        }
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+}
+
+fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{Error, ErrorKind},
+    path::PathBuf,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use chrono::Local;
+use dirs::config_dir;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data_structs::{JavaSelection, ProfileResolution},
+    dowloader::DEFAULT_INSTANCE_ID,
+    endpoints::Endpoints,
+    fs_util::write_atomic,
+    log_to_frontend,
+    memory_settings::{self, MemorySettings},
+};
+
+/// Name the settings had in json_struct_db, read once to migrate them.
+const LEGACY_SETTINGS_NAME: &str = "CanadaLauncher";
+/// Created once a settings file has existed, so the json_struct_db settings
+/// are never read again, even after the file is deleted or moved aside.
+const LEGACY_MIGRATED_MARKER: &str = ".legacy-settings-migrated";
+const SETTINGS_FILE: &str = "settings.json";
+/// Bump together with a new step in `migrate`.
+pub const SETTINGS_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub selected_instance: String,
+    pub instances: BTreeMap<String, InstanceSettings>,
+    pub launcher_path: Option<String>,
+    pub language: String,
+    pub channel: UpdateChannel,
+    pub theme: Theme,
+    /// Skip the network and launch with what is already installed.
+    pub offline_mode: bool,
+    /// HTTP(S) proxy for every request, like `http://host:port`.
+    pub proxy: Option<String>,
+    pub endpoints: Endpoints,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            selected_instance: DEFAULT_INSTANCE_ID.to_string(),
+            instances: BTreeMap::new(),
+            launcher_path: None,
+            language: "es".to_string(),
+            channel: UpdateChannel::Stable,
+            theme: Theme::System,
+            offline_mode: false,
+            proxy: None,
+            endpoints: Endpoints::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceSettings {
+    pub minecraft_version: String,
+    pub mod_loader: String,
+    pub mod_loader_version: String,
+    #[serde(deserialize_with = "memory_settings::deserialize_saved")]
+    pub memory: Option<MemorySettings>,
+    pub java: JavaSelection,
+    pub resolution: Option<ProfileResolution>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    Beta,
+    /// Also used for channels this version does not know.
+    #[default]
+    #[serde(other)]
+    Stable,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    #[serde(other)]
+    System,
+}
+
+impl Settings {
+    /// Settings of the selected instance.
+    pub fn instance(&self) -> InstanceSettings {
+        self.instances
+            .get(&self.selected_instance)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn instance_mut(&mut self) -> &mut InstanceSettings {
+        self.instances
+            .entry(self.selected_instance.clone())
+            .or_default()
+    }
+
+    pub fn launcher_path(&self) -> Option<&str> {
+        self.launcher_path
+            .as_deref()
+            .filter(|path| !path.is_empty())
+    }
+
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref().filter(|proxy| !proxy.is_empty())
+    }
+}
+
+fn parse_settings(json: &str) -> Result<Settings, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    if !value.is_object() {
+        return Err(serde::de::Error::custom("se esperaba un objeto JSON"));
+    }
+    serde_json::from_value(migrate(value))
+}
+
+/// Upgrades stored settings one schema version at a time. Files written
+/// before the version field existed are version 0. Newer versions are left
+/// as they are, so `store_settings` knows not to overwrite them.
+fn migrate(mut value: serde_json::Value) -> serde_json::Value {
+    let mut version = value
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        return value;
+    }
+    while version < SETTINGS_VERSION as u64 {
+        value = match version {
+            0 => migrate_from_data(value),
+            _ => value,
+        };
+        version += 1;
+    }
+    value["version"] = serde_json::json!(SETTINGS_VERSION);
+    value
+}
+
+/// Version 0 is the old flat `Data` struct, describing a single instance.
+fn migrate_from_data(data: serde_json::Value) -> serde_json::Value {
+    let instance_keys = [
+        "minecraft_version",
+        "mod_loader",
+        "mod_loader_version",
+        "memory",
+        "java",
+        "resolution",
+    ];
+    let instance: serde_json::Map<String, serde_json::Value> = instance_keys
+        .iter()
+        .filter_map(|key| Some((key.to_string(), data.get(*key)?.clone())))
+        .collect();
+
+    let mut settings = serde_json::json!({
+        "selected_instance": DEFAULT_INSTANCE_ID,
+        "instances": { DEFAULT_INSTANCE_ID: instance },
+    });
+    if let Some(launcher_path) = data.get("launcher_path") {
+        settings["launcher_path"] = launcher_path.clone();
+    }
+    settings
+}
+
+/// Problem found while loading the settings, kept until the UI asks for it
+/// since the first load usually happens before the window exists.
+static SETTINGS_WARNING: Mutex<Option<String>> = Mutex::new(None);
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+/// Last settings read from disk with the modification time of the file, so
+/// frequent loads, like one per HTTP request, skip reading and parsing it.
+static CACHE: Mutex<Option<(SystemTime, Settings)>> = Mutex::new(None);
+
+/// Directory for the launcher's own files, apart from the game instance.
+pub fn get_launcher_directory() -> PathBuf {
+    config_dir().unwrap_or_default().join("CanadaLauncher")
+}
+
+fn get_settings_path() -> PathBuf {
+    get_launcher_directory().join(SETTINGS_FILE)
+}
+
+/// Loads the settings, never failing: a file that cannot be parsed is moved
+/// aside, reported through `take_settings_warning` and replaced by defaults.
+/// The file is only read again when it changed since the last load.
+pub fn load_settings() -> Settings {
+    let path = get_settings_path();
+    let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+    if let (Ok(modified), Some((cached_modified, settings))) = (
+        &modified,
+        CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_ref(),
+    ) {
+        if modified == cached_modified {
+            return settings.clone();
+        }
+    }
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            if get_launcher_directory()
+                .join(LEGACY_MIGRATED_MARKER)
+                .exists()
+            {
+                return Settings::default();
+            }
+            return load_legacy_settings();
+        }
+        Err(e) => {
+            report_problem(format!(
+                "No se pudieron leer los ajustes ({}), usando los valores por defecto: {}",
+                path.display(),
+                e
+            ));
+            return Settings::default();
+        }
+    };
+
+    mark_legacy_migrated();
+    match parse_settings(&json) {
+        Ok(settings) => {
+            if settings.version > SETTINGS_VERSION {
+                report_problem(format!(
+                    "Los ajustes son de una versión más nueva de Canada Launcher ({}). Se usa lo que se entiende y no se guardarán cambios para no perder el resto.",
+                    settings.version
+                ));
+            }
+            if let Ok(modified) = modified {
+                *CACHE.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some((modified, settings.clone()));
+            }
+            settings
+        }
+        Err(e) => {
+            let mut corrupt_path = path.as_os_str().to_owned();
+            corrupt_path.push(format!(
+                ".corrupt-{}",
+                Local::now().format("%Y%m%d-%H%M%S%.3f")
+            ));
+            let corrupt_path = PathBuf::from(corrupt_path);
+            let moved = fs::rename(&path, &corrupt_path);
+            report_problem(match moved {
+                Ok(()) => format!(
+                    "Los ajustes estaban dañados ({}). Se guardaron en {} y se usan los valores por defecto.",
+                    e,
+                    corrupt_path.display()
+                ),
+                Err(rename_error) => format!(
+                    "Los ajustes estaban dañados ({}) y no se pudieron apartar: {}",
+                    e, rename_error
+                ),
+            });
+            Settings::default()
+        }
+    }
+}
+
+/// Settings saved by json_struct_db before the launcher owned the file.
+/// They are migrated once: storing them marks them as migrated.
+fn load_legacy_settings() -> Settings {
+    let Ok(json) = json_struct_db::read(LEGACY_SETTINGS_NAME) else {
+        mark_legacy_migrated();
+        return Settings::default();
+    };
+    match parse_settings(&json) {
+        Ok(settings) => {
+            if let Err(e) = store_settings(&settings) {
+                eprintln!("No se pudieron migrar los ajustes: {}", e);
+            }
+            settings
+        }
+        Err(e) => {
+            report_problem(format!(
+                "No se pudieron recuperar los ajustes anteriores, usando los valores por defecto: {}",
+                e
+            ));
+            mark_legacy_migrated();
+            Settings::default()
+        }
+    }
+}
+
+/// Writes the settings atomically, so a crash mid-save leaves the previous
+/// settings intact. Settings from a newer launcher are never written back,
+/// since this version would drop what it does not know.
+pub fn store_settings(settings: &Settings) -> Result<(), Error> {
+    if settings.version > SETTINGS_VERSION {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Los ajustes son de una versión más nueva de Canada Launcher, actualízalo para cambiarlos",
+        ));
+    }
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = get_settings_path();
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    write_atomic(&path, serde_json::to_string_pretty(settings)?.as_bytes())?;
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    mark_legacy_migrated();
+    Ok(())
+}
+
+fn mark_legacy_migrated() {
+    let marker = get_launcher_directory().join(LEGACY_MIGRATED_MARKER);
+    if !marker.exists() {
+        if let Err(e) =
+            fs::create_dir_all(get_launcher_directory()).and_then(|_| File::create(&marker))
+        {
+            eprintln!("No se pudo marcar la migración de los ajustes: {}", e);
+        }
+    }
+}
+
+/// Returns the last loading problem once, so the UI shows it a single time.
+pub fn take_settings_warning() -> Option<String> {
+    SETTINGS_WARNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+}
+
+fn report_problem(message: String) {
+    eprintln!("{}", message);
+    log_to_frontend(&message);
+    *SETTINGS_WARNING.lock().unwrap_or_else(|e| e.into_inner()) = Some(message);
+}
@@ -0,0 +1,92 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{fs, sync::Arc};
+
+use canadadowloader_lib::{
+    api_error::ApiError,
+    launcher_core,
+    settings::{get_launcher_directory, load_settings, store_settings},
+};
+use common::{lock, setup, MockResponse, RecordingSink};
+use serde_json::json;
+
+const FABRIC_INSTALLERS: &str = "/fabric-meta/v2/versions/installer";
+
+fn fabric_installers(versions: &[&str]) -> MockResponse {
+    MockResponse::json(json!(versions
+        .iter()
+        .map(|version| json!({ "url": "", "maven": "", "version": version, "stable": true }))
+        .collect::<Vec<_>>()))
+}
+
+/// Caches `data` as if it had been fetched on `fetched_at`.
+fn write_cache(name: &str, fetched_at: &str, data: serde_json::Value) {
+    let directory = get_launcher_directory().join("cache");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(
+        directory.join(format!("{}.json", name)),
+        json!({ "fetched_at": fetched_at, "data": data }).to_string(),
+    )
+    .unwrap();
+}
+
+fn set_offline_mode(offline_mode: bool) {
+    let mut settings = load_settings();
+    settings.offline_mode = offline_mode;
+    store_settings(&settings).unwrap();
+}
+
+#[tokio::test]
+async fn fresh_versions_come_from_the_cache_and_stale_ones_are_fetched() {
+    let _lock = lock().await;
+    let server = setup();
+    server.route(FABRIC_INSTALLERS, fabric_installers(&["1.0.1"]));
+    let hits = server.hits(FABRIC_INSTALLERS);
+
+    let sink = Arc::new(RecordingSink::default());
+    let versions = launcher_core::fabric_versions(sink.clone()).await.unwrap();
+    assert_eq!(versions, vec!["1.0.1"]);
+    assert_eq!(server.hits(FABRIC_INSTALLERS), hits + 1);
+
+    // Within the TTL the server is not asked again.
+    server.route(FABRIC_INSTALLERS, fabric_installers(&["1.0.2"]));
+    let versions = launcher_core::fabric_versions(sink.clone()).await.unwrap();
+    assert_eq!(versions, vec!["1.0.1"]);
+    assert_eq!(server.hits(FABRIC_INSTALLERS), hits + 1);
+
+    write_cache("fabric_versions", "2020-01-01T00:00:00Z", json!(["1.0.0"]));
+    let versions = launcher_core::fabric_versions(sink).await.unwrap();
+    assert_eq!(versions, vec!["1.0.2"]);
+    assert_eq!(server.hits(FABRIC_INSTALLERS), hits + 2);
+}
+
+#[tokio::test]
+async fn stale_versions_are_used_when_the_server_cannot_be_reached() {
+    let _lock = lock().await;
+    let server = setup();
+    write_cache("fabric_versions", "2020-01-01T00:00:00Z", json!(["0.9.0"]));
+    server.route(FABRIC_INSTALLERS, MockResponse::status(404));
+
+    let sink = Arc::new(RecordingSink::default());
+    let versions = launcher_core::fabric_versions(sink.clone()).await.unwrap();
+    assert_eq!(versions, vec!["0.9.0"]);
+    assert!(sink
+        .messages()
+        .iter()
+        .any(|message| message.starts_with("Sin conexión")));
+
+    // In offline mode the cache is used however old it is, without asking
+    // the server, and nothing cached is an error.
+    set_offline_mode(true);
+    let requests = server.requests().len();
+    let versions = launcher_core::fabric_versions(sink.clone()).await;
+    let manifest = launcher_core::minecraft_manifest(sink, None).await;
+    let new_requests = server.requests().len() - requests;
+    set_offline_mode(false);
+
+    assert_eq!(versions.unwrap(), vec!["0.9.0"]);
+    assert!(matches!(manifest, Err(ApiError::Offline(_))));
+    assert_eq!(new_requests, 0);
+}
@@ -0,0 +1,378 @@
+//! Local stand-ins for S3 and the Fabric, Forge and Mojang metadata servers,
+//! plus an isolated home directory, so the tests never touch the network or
+//! the player's real files.
+#![allow(dead_code)]
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+
+use canadadowloader_lib::{
+    dowloader::{set_bucket_configs, BucketConfig},
+    endpoints::Endpoints,
+    events::EventSink,
+    settings::{store_settings, Settings},
+};
+
+#[derive(Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn json(body: serde_json::Value) -> Self {
+        MockResponse {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub fn bytes(body: &[u8]) -> Self {
+        MockResponse {
+            status: 200,
+            content_type: "application/octet-stream",
+            body: body.to_vec(),
+        }
+    }
+
+    pub fn xml(status: u16, body: String) -> Self {
+        MockResponse {
+            status,
+            content_type: "application/xml",
+            body: body.into_bytes(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        MockResponse {
+            status,
+            content_type: "text/plain",
+            body: Vec::new(),
+        }
+    }
+
+    /// What S3 answers for a missing object or bucket.
+    fn no_such_key() -> Self {
+        Self::xml(
+            404,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message></Error>"
+                .to_string(),
+        )
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Responses by path. The front one is served and removed while more
+    /// remain, so the last one keeps being served.
+    routes: HashMap<String, VecDeque<MockResponse>>,
+    /// Objects of every bucket served with `bucket`, by key.
+    buckets: HashMap<String, BTreeMap<String, Vec<u8>>>,
+    /// `METHOD /path?query` of every request received.
+    requests: Vec<String>,
+}
+
+impl State {
+    /// Answers path-style S3 requests for the served buckets: listing, and
+    /// getting, putting and deleting objects. The listing ignores the query,
+    /// so it always holds every key.
+    fn s3(&mut self, method: &str, path: &str, body: Vec<u8>) -> Option<MockResponse> {
+        let path = percent_decode(path.strip_prefix('/')?);
+        let (bucket, key) = path.split_once('/').unwrap_or((&path, ""));
+        let objects = self.buckets.get_mut(bucket)?;
+        let response = match (method, key) {
+            ("GET", "") => MockResponse::xml(200, list_bucket(bucket, objects)),
+            ("GET" | "HEAD", key) => match objects.get(key) {
+                Some(object) => MockResponse::bytes(object),
+                None => MockResponse::no_such_key(),
+            },
+            ("PUT", key) => {
+                objects.insert(key.to_string(), body);
+                MockResponse::status(200)
+            }
+            ("DELETE", key) => {
+                objects.remove(key);
+                MockResponse::status(204)
+            }
+            _ => MockResponse::status(405),
+        };
+        Some(response)
+    }
+}
+
+fn list_bucket(bucket: &str, objects: &BTreeMap<String, Vec<u8>>) -> String {
+    let contents: String = objects
+        .iter()
+        .map(|(key, body)| {
+            format!(
+                "<Contents><Key>{}</Key><LastModified>2020-01-01T00:00:00.000Z</LastModified>\
+                 <ETag>\"etag\"</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                key,
+                body.len()
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\
+         <Name>{}</Name><Prefix></Prefix><KeyCount>{}</KeyCount><MaxKeys>1000</MaxKeys>\
+         <IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+        bucket,
+        objects.len(),
+        contents
+    )
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value.get(i + 1..i + 3);
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Minimal HTTP/1.1 server answering from a route table, one connection per
+/// request.
+pub struct MockServer {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = server_state.clone();
+                thread::spawn(move || handle(stream, &state));
+            }
+        });
+        MockServer { url, state }
+    }
+
+    pub fn route(&self, path: &str, response: MockResponse) {
+        self.route_sequence(path, vec![response]);
+    }
+
+    pub fn route_sequence(&self, path: &str, responses: Vec<MockResponse>) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert(path.to_string(), responses.into());
+    }
+
+    pub fn remove(&self, path: &str) {
+        self.state.lock().unwrap().routes.remove(path);
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Number of requests received for `path`, ignoring the query.
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter_map(|request| request.split_once(' '))
+            .filter(|(_, target)| {
+                target
+                    .split('?')
+                    .next()
+                    .map(|target| target.trim_end_matches('/'))
+                    == Some(path)
+            })
+            .count()
+    }
+
+    /// Serves `bucket` as a path-style S3 bucket holding `objects`, replacing
+    /// whatever it held before.
+    pub fn bucket(&self, bucket: &str, objects: &[(&str, &[u8])]) {
+        let objects = objects
+            .iter()
+            .map(|(key, body)| (key.to_string(), body.to_vec()))
+            .collect();
+        self.state
+            .lock()
+            .unwrap()
+            .buckets
+            .insert(bucket.to_string(), objects);
+    }
+
+    /// Current contents of a bucket served with `bucket`.
+    pub fn objects(&self, bucket: &str) -> BTreeMap<String, Vec<u8>> {
+        self.state.lock().unwrap().buckets[bucket].clone()
+    }
+
+    /// Endpoints sending every service to this server, each under its own
+    /// path prefix.
+    pub fn endpoints(&self) -> Endpoints {
+        let at = |service: &str| format!("{}/{}", self.url, service);
+        Endpoints {
+            fabric_meta: at("fabric-meta"),
+            fabric_maven: at("fabric-maven"),
+            forge_files: at("forge-files"),
+            forge_maven: at("forge-maven"),
+            mojang_meta: at("mojang-meta"),
+            mojang_piston_meta: at("piston-meta"),
+            mojang_piston_data: at("piston-data"),
+            mojang_libraries: at("libraries"),
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).unwrap_or(0) == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    // Path-style S3 lists a bucket as `/bucket/`.
+    let path = target
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(format!("{} {}", method, target));
+        match state.routes.get_mut(path) {
+            Some(responses) if responses.len() > 1 => responses.pop_front().unwrap(),
+            Some(responses) => responses.front().cloned().unwrap(),
+            None => state
+                .s3(&method, path, body)
+                .unwrap_or_else(MockResponse::no_such_key),
+        }
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    if method != "HEAD" {
+        let _ = stream.write_all(&response.body);
+    }
+    let _ = stream.flush();
+}
+
+/// Keeps every message reported to it.
+#[derive(Default)]
+pub struct RecordingSink {
+    messages: Mutex<Vec<String>>,
+}
+
+impl RecordingSink {
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for RecordingSink {
+    fn log(&self, message: &str) {
+        self.messages.lock().unwrap().push(message.to_string());
+    }
+}
+
+/// Buckets `dowload_mods` syncs into the instance's mods and resource packs.
+pub const MODS_BUCKET: &str = "pack-mods";
+pub const RESOURCE_PACKS_BUCKET: &str = "pack-resourcepacks";
+
+/// Starts the mock server and points the launcher's config directory and
+/// buckets at temporary, mocked ones. Shared by all the tests of a binary,
+/// since the environment is process wide. The config directory is only read
+/// from the environment on Linux, so the tests only build there.
+pub fn setup() -> &'static MockServer {
+    static SERVER: OnceLock<MockServer> = OnceLock::new();
+    SERVER.get_or_init(|| {
+        let home = env::temp_dir().join(format!("canada-launcher-tests-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        env::set_var("XDG_CONFIG_HOME", &home);
+        // Only Linux reads the config directory from the environment.
+        assert_eq!(
+            dirs::config_dir(),
+            Some(home.clone()),
+            "the tests cannot redirect the config directory on this platform"
+        );
+        env::set_var("NO_PROXY", "127.0.0.1,localhost");
+
+        let server = MockServer::start();
+        let bucket = |bucket: &str| BucketConfig {
+            access_key: "test".to_string(),
+            secret_key: "test".to_string(),
+            endpoint: server.url.clone(),
+            bucket: bucket.to_string(),
+            force_path_style: true,
+        };
+        set_bucket_configs(bucket(MODS_BUCKET), bucket(RESOURCE_PACKS_BUCKET));
+        let settings = Settings {
+            endpoints: server.endpoints(),
+            ..Settings::default()
+        };
+        store_settings(&settings).unwrap();
+        server
+    })
+}
+
+/// For the tests of a binary that share a file, like settings.json or
+/// launcher_profiles.json, so they take turns.
+pub async fn lock() -> tokio::sync::MutexGuard<'static, ()> {
+    static LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    setup();
+    LOCK.lock().await
+}
+
+/// A scratch directory below the isolated config directory.
+pub fn temp_directory(name: &str) -> PathBuf {
+    let directory = dirs::config_dir().unwrap().join("scratch").join(name);
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
@@ -0,0 +1,471 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{
+    fs,
+    io::{Cursor, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::Once,
+};
+
+use canadadowloader_lib::{
+    data_structs::ModLoaders,
+    dowloader::{
+        get_instance_directory, get_minecraft_canada_directory, get_minecraft_directory,
+        start_install, InstallError, InstanceOptions, LoaderInstaller,
+    },
+};
+use common::{setup, temp_directory, MockResponse, MockServer, MODS_BUCKET, RESOURCE_PACKS_BUCKET};
+use serde_json::json;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+const CLIENT_JAR: &[u8] = b"client jar";
+/// SHA-1 of `CLIENT_JAR`.
+const CLIENT_JAR_SHA1: &str = "e0ede96191ad4f38d167edb4cad56c99fda06502";
+
+/// Serves a Mojang manifest with `1.0-test`, `2.0-test` and `tampered-test`,
+/// whose client jar has the right size but not the listed hash. The manifest links
+/// the real Mojang hosts, which the configured endpoints mirror to the mock.
+fn serve_minecraft_versions(server: &MockServer) {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let ids = ["1.0-test", "2.0-test", "tampered-test"];
+        let versions: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "type": "release",
+                    "url": format!("https://piston-meta.mojang.com/v1/packages/{}.json", id),
+                    "time": "2024-01-01T00:00:00+00:00",
+                    "releaseTime": "2024-01-01T00:00:00+00:00",
+                })
+            })
+            .collect();
+        server.route(
+            "/mojang-meta/mc/game/version_manifest.json",
+            MockResponse::json(json!({
+                "latest": { "release": "2.0-test", "snapshot": "2.0-test" },
+                "versions": versions,
+            })),
+        );
+        for id in ids {
+            server.route(
+                &format!("/piston-meta/v1/packages/{}.json", id),
+                MockResponse::json(json!({
+                    "id": id,
+                    "downloads": {
+                        "client": {
+                            "url": format!("https://piston-data.mojang.com/v1/objects/{}/client.jar", id),
+                            "sha1": CLIENT_JAR_SHA1,
+                            "size": CLIENT_JAR.len(),
+                        },
+                    },
+                })),
+            );
+            let client_jar = match id {
+                "tampered-test" => b"client jaX",
+                _ => CLIENT_JAR,
+            };
+            server.route(
+                &format!("/piston-data/v1/objects/{}/client.jar", id),
+                MockResponse::bytes(client_jar),
+            );
+        }
+    });
+}
+
+fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in entries {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// Installs a Java runtime whose `java` writes its arguments to `record`,
+/// one per line, after a `---` line for each run.
+fn install_recording_java(runtime_directory: &Path, record: &Path) {
+    let runtime = runtime_directory.join("jre-legacy");
+    let java = runtime.join("bin/java");
+    fs::create_dir_all(java.parent().unwrap()).unwrap();
+    fs::write(
+        &java,
+        format!(
+            "#!/bin/sh\n{{ echo ---; printf '%s\\n' \"$@\"; }} >> '{}'\n",
+            record.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&java, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(runtime.join(".canada-runtime"), "8").unwrap();
+}
+
+#[tokio::test]
+async fn vanilla_install_downloads_the_version_from_the_mirror() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let minecraft_directory = temp_directory("vanilla");
+    let installer = LoaderInstaller {
+        loader: ModLoaders::Vanilla,
+        minecraft_version: "1.0-test".to_string(),
+        loader_version: String::new(),
+        minecraft_directory: minecraft_directory.clone(),
+        runtime_directory: minecraft_directory.join("runtime"),
+        logs_directory: minecraft_directory.join("logs"),
+    };
+
+    assert!(!installer.is_version_installed());
+    installer.install_loader().await.unwrap();
+
+    let version_directory = minecraft_directory.join("versions/1.0-test");
+    assert_eq!(
+        fs::read(version_directory.join("1.0-test.jar")).unwrap(),
+        CLIENT_JAR
+    );
+    assert!(version_directory.join("1.0-test.json").exists());
+    assert!(installer.is_version_installed());
+
+    // Installed versions are not downloaded again.
+    installer.install_loader().await.unwrap();
+    assert_eq!(
+        server.hits("/piston-data/v1/objects/1.0-test/client.jar"),
+        1
+    );
+}
+
+#[test]
+fn mirror_only_rewrites_the_default_hosts() {
+    let server = setup();
+    let endpoints = server.endpoints();
+    assert_eq!(
+        endpoints.mirror("https://libraries.minecraft.net/a/b.jar"),
+        format!("{}/libraries/a/b.jar", server.url)
+    );
+    assert_eq!(
+        endpoints.mirror("https://libraries.minecraft.net.example.com/a/b.jar"),
+        "https://libraries.minecraft.net.example.com/a/b.jar"
+    );
+}
+
+#[tokio::test]
+async fn vanilla_install_rejects_unknown_versions() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let minecraft_directory = temp_directory("unknown");
+    let installer = LoaderInstaller {
+        loader: ModLoaders::Vanilla,
+        minecraft_version: "0.0-missing".to_string(),
+        loader_version: String::new(),
+        minecraft_directory: minecraft_directory.clone(),
+        runtime_directory: minecraft_directory.join("runtime"),
+        logs_directory: minecraft_directory.join("logs"),
+    };
+
+    let result = installer.install_loader().await;
+    assert!(matches!(result, Err(InstallError::InvalidData(_))));
+}
+
+#[tokio::test]
+async fn offline_managed_java_never_downloads_the_runtime() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let minecraft_directory = temp_directory("offline-java");
+    let installer = LoaderInstaller {
+        loader: ModLoaders::Vanilla,
+        minecraft_version: "2.0-test".to_string(),
+        loader_version: String::new(),
+        minecraft_directory: minecraft_directory.clone(),
+        runtime_directory: minecraft_directory.join("runtime"),
+        logs_directory: minecraft_directory.join("logs"),
+    };
+
+    let result = installer.get_managed_java(true).await;
+    assert!(matches!(result, Err(InstallError::Java(_))));
+
+    installer.install_loader().await.unwrap();
+    let result = installer.get_managed_java(true).await;
+    assert!(matches!(result, Err(InstallError::Java(_))));
+    assert!(!server
+        .requests()
+        .iter()
+        .any(|request| request.contains("java-runtime")));
+}
+
+#[tokio::test]
+async fn vanilla_install_rejects_a_client_jar_with_the_wrong_hash() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let minecraft_directory = temp_directory("tampered");
+    let installer = LoaderInstaller {
+        loader: ModLoaders::Vanilla,
+        minecraft_version: "tampered-test".to_string(),
+        loader_version: String::new(),
+        minecraft_directory: minecraft_directory.clone(),
+        runtime_directory: minecraft_directory.join("runtime"),
+        logs_directory: minecraft_directory.join("logs"),
+    };
+
+    let result = installer.install_loader().await;
+    assert!(matches!(result, Err(InstallError::InvalidData(_))));
+    assert!(!minecraft_directory
+        .join("versions/tampered-test/tampered-test.jar")
+        .exists());
+    assert!(!installer.is_version_installed());
+
+    // A folder left by an older, interrupted install is not enough either,
+    // so the next run downloads the client again.
+    fs::create_dir_all(minecraft_directory.join("versions/tampered-test")).unwrap();
+    assert!(!installer.is_version_installed());
+    let client_jar = "/piston-data/v1/objects/tampered-test/client.jar";
+    let hits = server.hits(client_jar);
+    assert!(installer.install_loader().await.is_err());
+    assert_eq!(server.hits(client_jar), hits + 1);
+}
+
+#[tokio::test]
+async fn start_install_syncs_the_pack_and_writes_the_profile() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let pack = json!({
+        "servers": [{ "name": "Canada", "ip": "mc.example.com" }],
+        "options": { "defaults": { "renderDistance": 8 } },
+    });
+    server.bucket(
+        MODS_BUCKET,
+        &[
+            ("sodium.jar", b"sodium"),
+            ("pack.json", pack.to_string().as_bytes()),
+        ],
+    );
+    server.bucket(RESOURCE_PACKS_BUCKET, &[("faithful.zip", b"zip")]);
+
+    start_install(
+        "default",
+        ModLoaders::Vanilla,
+        String::new(),
+        "2.0-test".to_string(),
+        InstanceOptions {
+            java_args: Some("-Xmx2G".to_string()),
+            ..InstanceOptions::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let instance = get_minecraft_canada_directory();
+    assert!(instance.join("mods/sodium.jar").exists());
+    assert!(instance.join("resourcepacks/faithful.zip").exists());
+    assert!(instance.join("servers.dat").exists());
+    let options = fs::read_to_string(instance.join("options.txt")).unwrap();
+    assert!(options.lines().any(|line| line == "renderDistance:8"));
+
+    let minecraft = get_minecraft_directory();
+    assert!(minecraft.join("versions/2.0-test/2.0-test.jar").exists());
+    let profiles: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(minecraft.join("launcher_profiles.json")).unwrap(),
+    )
+    .unwrap();
+    let profile = &profiles["profiles"]["canada-default"];
+    assert_eq!(profile["lastVersionId"], "2.0-test");
+    assert_eq!(profile["javaArgs"], "-Xmx2G");
+    assert_eq!(profile["gameDir"], instance.to_string_lossy().as_ref());
+    assert_eq!(profile["type"], "custom");
+
+    // Without saved memory settings or a Java of our own, the player's
+    // javaArgs and javaDir are kept on every install.
+    let mut profiles = profiles;
+    profiles["profiles"]["canada-default"]["javaArgs"] = json!("-Xmx3G -Dcustom=1");
+    profiles["profiles"]["canada-default"]["javaDir"] = json!("/opt/jdk/bin/java");
+    fs::write(
+        minecraft.join("launcher_profiles.json"),
+        profiles.to_string(),
+    )
+    .unwrap();
+    for _ in 0..2 {
+        start_install(
+            "default",
+            ModLoaders::Vanilla,
+            String::new(),
+            "2.0-test".to_string(),
+            InstanceOptions::default(),
+        )
+        .await
+        .unwrap();
+        let profiles: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(minecraft.join("launcher_profiles.json")).unwrap(),
+        )
+        .unwrap();
+        let profile = &profiles["profiles"]["canada-default"];
+        assert_eq!(profile["javaArgs"], "-Xmx3G -Dcustom=1");
+        assert_eq!(profile["javaDir"], "/opt/jdk/bin/java");
+    }
+
+    // Another instance gets its own game directory and profile name.
+    start_install(
+        "beta",
+        ModLoaders::Vanilla,
+        String::new(),
+        "2.0-test".to_string(),
+        InstanceOptions::default(),
+    )
+    .await
+    .unwrap();
+    let beta = get_instance_directory("beta");
+    assert_ne!(beta, instance);
+    assert!(beta.join("mods/sodium.jar").exists());
+    assert!(beta.join("servers.dat").exists());
+    let profiles: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(minecraft.join("launcher_profiles.json")).unwrap(),
+    )
+    .unwrap();
+    let profile = &profiles["profiles"]["canada-beta"];
+    assert_eq!(profile["gameDir"], beta.to_string_lossy().as_ref());
+    assert_ne!(
+        profile["name"],
+        profiles["profiles"]["canada-default"]["name"]
+    );
+}
+
+#[tokio::test]
+async fn forge_install_resolves_libraries_and_processor_arguments() {
+    let server = setup();
+    serve_minecraft_versions(server);
+    let minecraft_directory = temp_directory("forge");
+    let installer = LoaderInstaller {
+        loader: ModLoaders::Forge,
+        minecraft_version: "2.0-test".to_string(),
+        loader_version: "9.9.9".to_string(),
+        minecraft_directory: minecraft_directory.clone(),
+        runtime_directory: minecraft_directory.join("runtime"),
+        logs_directory: minecraft_directory.join("logs"),
+    };
+    let record = minecraft_directory.join("java-arguments.txt");
+    install_recording_java(&installer.runtime_directory, &record);
+
+    let install_profile = json!({
+        "json": "/version.json",
+        "data": {
+            "MAPPINGS": { "client": "[de.oceanlabs.mcp:mcp_config:2.0-test:mappings@txt]" },
+            "BINPATCH": { "client": "/data/client.lzma" },
+            "LABEL": { "client": "'Canada'" },
+        },
+        "libraries": [{
+            "name": "net.minecraftforge:installertools:1.0",
+            "downloads": { "artifact": {
+                "path": "net/minecraftforge/installertools/1.0/installertools-1.0.jar",
+                "url": "",
+            } },
+        }],
+        "processors": [
+            {
+                "jar": "net.minecraftforge:installertools:1.0",
+                "classpath": ["net.minecraftforge:srgutils:0.4"],
+                "args": [
+                    "{MINECRAFT_JAR}",
+                    "--mappings={MAPPINGS}",
+                    "{BINPATCH}",
+                    "{LABEL}",
+                    "[net.minecraft:client:2.0-test:srg]",
+                    "'{SIDE}'",
+                    "{SIDE}",
+                ],
+            },
+            { "sides": ["server"], "jar": "net.minecraftforge:installertools:1.0" },
+        ],
+    });
+    let version = json!({
+        "id": "2.0-test-forge-9.9.9",
+        "libraries": [
+            { "name": "net.minecraftforge:srgutils:0.4" },
+            { "name": "org.example:natives:2.0:linux@zip", "url": format!("{}/forge-maven/", server.url) },
+        ],
+    });
+    let processor_jar = zip(&[(
+        "META-INF/MANIFEST.MF",
+        b"Manifest-Version: 1.0\r\nMain-Class: net.minecraftforge.installertools.\r\n ConsoleTool\r\n",
+    )]);
+    server.route(
+        "/forge-maven/net/minecraftforge/forge/2.0-test-9.9.9/forge-2.0-test-9.9.9-installer.jar",
+        MockResponse::bytes(&zip(&[
+            (
+                "install_profile.json",
+                install_profile.to_string().as_bytes(),
+            ),
+            ("version.json", version.to_string().as_bytes()),
+            ("data/client.lzma", b"patches"),
+            (
+                "maven/net/minecraftforge/installertools/1.0/installertools-1.0.jar",
+                &processor_jar,
+            ),
+        ])),
+    );
+    server.route(
+        "/libraries/net/minecraftforge/srgutils/0.4/srgutils-0.4.jar",
+        MockResponse::bytes(b"srgutils"),
+    );
+    server.route(
+        "/forge-maven/org/example/natives/2.0/natives-2.0-linux.zip",
+        MockResponse::bytes(b"natives"),
+    );
+
+    installer.install_loader().await.unwrap();
+    assert!(installer.is_version_installed());
+
+    // Libraries land at their maven paths, from the installer or the server.
+    let libraries = minecraft_directory.join("libraries");
+    let library = |path: &str| libraries.join(path).to_string_lossy().to_string();
+    assert_eq!(
+        fs::read(libraries.join("net/minecraftforge/installertools/1.0/installertools-1.0.jar"))
+            .unwrap(),
+        processor_jar
+    );
+    assert_eq!(
+        fs::read(libraries.join("net/minecraftforge/srgutils/0.4/srgutils-0.4.jar")).unwrap(),
+        b"srgutils"
+    );
+    assert_eq!(
+        fs::read(libraries.join("org/example/natives/2.0/natives-2.0-linux.zip")).unwrap(),
+        b"natives"
+    );
+
+    // Only the client processor runs, with its tokens replaced.
+    let arguments = fs::read_to_string(&record).unwrap();
+    let arguments: Vec<&str> = arguments.lines().collect();
+    let binpatch = arguments[6];
+    assert_eq!(
+        arguments,
+        vec![
+            "---".to_string(),
+            "-cp".to_string(),
+            format!(
+                "{}:{}",
+                library("net/minecraftforge/installertools/1.0/installertools-1.0.jar"),
+                library("net/minecraftforge/srgutils/0.4/srgutils-0.4.jar")
+            ),
+            "net.minecraftforge.installertools.ConsoleTool".to_string(),
+            minecraft_directory
+                .join("versions/2.0-test/2.0-test.jar")
+                .to_string_lossy()
+                .to_string(),
+            format!(
+                "--mappings={}",
+                library("de/oceanlabs/mcp/mcp_config/2.0-test/mcp_config-2.0-test-mappings.txt")
+            ),
+            binpatch.to_string(),
+            "Canada".to_string(),
+            library("net/minecraft/client/2.0-test/client-2.0-test-srg.jar"),
+            "{SIDE}".to_string(),
+            "client".to_string(),
+        ]
+    );
+    // Installer files are extracted to a work directory that is removed
+    // once the processors are done.
+    assert!(binpatch.ends_with("/data/client.lzma"));
+    assert!(!Path::new(binpatch).exists());
+}
@@ -0,0 +1,238 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use canadadowloader_lib::{
+    data_structs::{ModLoaders, PackManifest},
+    dowloader::{
+        create_profile, get_instance_directory, get_loader_installer, get_minecraft_directory,
+        list_profile_backups, remove_instance_profile, restore_profile_backup, InstanceOptions,
+    },
+};
+use common::lock;
+use nbt::{Blob, Value};
+use serde_json::json;
+
+/// Writes the profile of `instance_id` with the pack described by `pack`,
+/// as an install does once the loader is in place.
+async fn write_profile(instance_id: &str, pack: serde_json::Value) {
+    // Installing the loader creates the game directory.
+    fs::create_dir_all(get_minecraft_directory()).unwrap();
+    let pack_manifest: PackManifest = serde_json::from_value(pack).unwrap();
+    let loader_installer =
+        get_loader_installer(ModLoaders::Vanilla, String::new(), "1.0-test".to_string());
+    create_profile(
+        instance_id,
+        &loader_installer,
+        InstanceOptions::default(),
+        pack_manifest,
+    )
+    .await
+    .unwrap();
+}
+
+fn read_profiles() -> serde_json::Value {
+    let path = get_minecraft_directory().join("launcher_profiles.json");
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn read_options(instance_id: &str) -> Vec<String> {
+    let path = get_instance_directory(instance_id).join("options.txt");
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+fn server(name: &str, ip: &str) -> Value {
+    Value::Compound(
+        [
+            ("name".to_string(), Value::String(name.to_string())),
+            ("ip".to_string(), Value::String(ip.to_string())),
+        ]
+        .into_iter()
+        .collect(),
+    )
+}
+
+fn read_servers(path: &Path) -> Vec<Value> {
+    let blob = Blob::from_reader(&mut fs::File::open(path).unwrap()).unwrap();
+    match blob.get("servers") {
+        Some(Value::List(servers)) => servers.clone(),
+        other => panic!("servers.dat has no server list: {:?}", other),
+    }
+}
+
+fn server_field<'a>(server: &'a Value, field: &str) -> Option<&'a Value> {
+    match server {
+        Value::Compound(entry) => entry.get(field),
+        other => panic!("not a server entry: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn profile_backups_are_rotated_and_restored() {
+    let _lock = lock().await;
+    let minecraft = get_minecraft_directory();
+    fs::create_dir_all(&minecraft).unwrap();
+    let _ = fs::remove_file(minecraft.join("launcher_profiles.json"));
+    for backup in list_profile_backups().unwrap() {
+        fs::remove_file(minecraft.join(backup)).unwrap();
+    }
+    let old_backups: Vec<String> = (0..12)
+        .map(|i| {
+            format!(
+                "launcher_profiles.json.canada-backup-20200101-000000.{:03}",
+                i
+            )
+        })
+        .collect();
+    for backup in &old_backups {
+        fs::write(minecraft.join(backup), r#"{"profiles": {}}"#).unwrap();
+    }
+
+    write_profile("default", json!({})).await;
+    write_profile("beta", json!({})).await;
+
+    // Only the newest ten are kept, the one just taken first.
+    let backups = list_profile_backups().unwrap();
+    assert_eq!(backups.len(), 10);
+    assert!(!old_backups[..3].iter().any(|old| backups.contains(old)));
+    let kept: Vec<&String> = old_backups[3..].iter().rev().collect();
+    assert_eq!(backups[1..].iter().collect::<Vec<_>>(), kept);
+
+    assert!(remove_instance_profile("beta").unwrap());
+    assert!(read_profiles()["profiles"].get("canada-beta").is_none());
+
+    // The newest backup is the one taken before the removal.
+    restore_profile_backup(None).unwrap();
+    let profiles = read_profiles();
+    assert!(profiles["profiles"].get("canada-default").is_some());
+    assert!(profiles["profiles"].get("canada-beta").is_some());
+
+    restore_profile_backup(Some(&old_backups[11])).unwrap();
+    assert_eq!(read_profiles(), json!({ "profiles": {} }));
+    assert_eq!(list_profile_backups().unwrap().len(), 10);
+    assert!(restore_profile_backup(Some("launcher_profiles.json.missing")).is_err());
+}
+
+#[tokio::test]
+async fn pack_servers_are_merged_into_the_players_server_list() {
+    let _lock = lock().await;
+    let servers_dat = get_instance_directory("servers").join("servers.dat");
+    fs::create_dir_all(servers_dat.parent().unwrap()).unwrap();
+    let mut friend = server("Friends", "play.friends.net");
+    if let Value::Compound(entry) = &mut friend {
+        entry.insert("acceptTextures".to_string(), Value::Byte(1));
+    }
+    let mut blob = Blob::new();
+    blob.insert(
+        "servers",
+        Value::List(vec![
+            friend.clone(),
+            server("Canada (old)", "MC.example.com"),
+        ]),
+    )
+    .unwrap();
+    blob.to_writer(&mut fs::File::create(&servers_dat).unwrap())
+        .unwrap();
+
+    let pack = json!({
+        "servers": [
+            { "name": "Canada", "ip": "mc.example.com", "icon": "data:image/png;base64,aWNvbg==" },
+            { "name": "Canada Creative", "ip": "creative.example.com" },
+        ],
+    });
+    // Installing again neither duplicates nor reorders anything.
+    for _ in 0..2 {
+        write_profile("servers", pack.clone()).await;
+
+        let servers = read_servers(&servers_dat);
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers[0], friend);
+        assert_eq!(
+            server_field(&servers[1], "name"),
+            Some(&Value::String("Canada".to_string()))
+        );
+        assert_eq!(
+            server_field(&servers[1], "ip"),
+            Some(&Value::String("mc.example.com".to_string()))
+        );
+        assert_eq!(
+            server_field(&servers[1], "icon"),
+            Some(&Value::String("aWNvbg==".to_string()))
+        );
+        assert_eq!(
+            servers[2],
+            server("Canada Creative", "creative.example.com")
+        );
+    }
+}
+
+#[tokio::test]
+async fn only_enforced_options_overwrite_the_players_choices() {
+    let _lock = lock().await;
+    let options_path = get_instance_directory("options").join("options.txt");
+    fs::create_dir_all(options_path.parent().unwrap()).unwrap();
+    fs::write(&options_path, "guiScale:3\nfov:0.5\n").unwrap();
+    let pack = json!({
+        "options": {
+            "defaults": { "renderDistance": 8, "lang": "es_es", "guiScale": 2 },
+            "enforced": ["lang"],
+        },
+    });
+
+    // The first install fills in what the player has not set.
+    write_profile("options", pack.clone()).await;
+    assert_eq!(
+        read_options("options"),
+        vec!["guiScale:3", "fov:0.5", "lang:es_es", "renderDistance:8"]
+    );
+
+    // Later installs only bring back the enforced ones.
+    fs::write(&options_path, "guiScale:3\nfov:0.5\nlang:en_us\n").unwrap();
+    write_profile("options", pack).await;
+    assert_eq!(
+        read_options("options"),
+        vec!["guiScale:3", "fov:0.5", "lang:es_es"]
+    );
+}
+
+#[tokio::test]
+async fn resource_packs_follow_the_pack_order_and_removals() {
+    let _lock = lock().await;
+    let instance = get_instance_directory("packs");
+    let resource_packs = instance.join("resourcepacks");
+    fs::create_dir_all(&resource_packs).unwrap();
+    for pack in ["a.zip", "b.zip", "mine.zip"] {
+        fs::write(resource_packs.join(pack), pack).unwrap();
+    }
+    fs::write(
+        instance.join("options.txt"),
+        "resourcePacks:[\"vanilla\",\"file/mine.zip\"]\n",
+    )
+    .unwrap();
+
+    // The first listed pack has the top priority, so it goes last. Packs
+    // that were not synced are skipped.
+    write_profile(
+        "packs",
+        json!({ "resource_packs": ["b.zip", "a.zip", "missing.zip"] }),
+    )
+    .await;
+    assert_eq!(
+        read_options("packs"),
+        vec![r#"resourcePacks:["vanilla","file/mine.zip","file/a.zip","file/b.zip"]"#]
+    );
+
+    // A pack removed from the bucket is disabled, the player's own stay.
+    fs::remove_file(resource_packs.join("b.zip")).unwrap();
+    write_profile("packs", json!({ "resource_packs": ["a.zip"] })).await;
+    assert_eq!(
+        read_options("packs"),
+        vec![r#"resourcePacks:["vanilla","file/mine.zip","file/a.zip"]"#]
+    );
+}
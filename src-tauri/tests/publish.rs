@@ -0,0 +1,114 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use canadadowloader_lib::{
+    data_structs::PackManifest,
+    dowloader::{
+        dowload_mods, get_minecraft_canada_directory, publish_pack, InstallError,
+        DEFAULT_INSTANCE_ID,
+    },
+};
+use common::{setup, temp_directory, MODS_BUCKET, RESOURCE_PACKS_BUCKET};
+use serde_json::json;
+
+fn write(root: &Path, path: &str, contents: &[u8]) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn published_manifest() -> PackManifest {
+    serde_json::from_slice(&setup().objects(MODS_BUCKET)["pack.json"]).unwrap()
+}
+
+#[tokio::test]
+async fn publish_uploads_changes_and_removes_stale_objects() {
+    let server = setup();
+    server.bucket(
+        MODS_BUCKET,
+        &[("old.jar", b"old"), ("sodium.jar", b"sodium")],
+    );
+    server.bucket(RESOURCE_PACKS_BUCKET, &[]);
+    let root = temp_directory("publish");
+    write(&root, "mods/sodium.jar", b"sodium");
+    write(&root, "mods/lithium.jar", b"lithium");
+    write(&root, "config/sodium/options.json", b"{}");
+    write(&root, "resourcepacks/faithful.zip", b"zip");
+    write(
+        &root,
+        "pack.json",
+        json!({ "servers": [{ "name": "Canada", "ip": "mc.example.com" }] })
+            .to_string()
+            .as_bytes(),
+    );
+
+    // A dry run only reports.
+    let before = server.objects(MODS_BUCKET);
+    let report = publish_pack(&root, true).await.unwrap();
+    assert_eq!(report.deleted, vec!["pack-mods/old.jar"]);
+    assert_eq!(report.uploaded.len(), 5);
+    assert_eq!(server.objects(MODS_BUCKET), before);
+    assert!(server.objects(RESOURCE_PACKS_BUCKET).is_empty());
+
+    // Without published hashes every file is uploaded once.
+    let report = publish_pack(&root, false).await.unwrap();
+    assert_eq!(report.unchanged, 0);
+    let mods = server.objects(MODS_BUCKET);
+    assert_eq!(
+        mods.keys().collect::<Vec<_>>(),
+        vec![
+            "config/sodium/options.json",
+            "lithium.jar",
+            "pack.json",
+            "sodium.jar"
+        ]
+    );
+    assert_eq!(
+        server.objects(RESOURCE_PACKS_BUCKET)["faithful.zip"],
+        b"zip"
+    );
+    let manifest = published_manifest();
+    assert_eq!(manifest.servers[0].ip, "mc.example.com");
+    let sodium = manifest
+        .files
+        .iter()
+        .find(|file| file.path == "mods/sodium.jar")
+        .unwrap();
+    assert_eq!(sodium.sha1, "6f07dc1d0a287f925d7672e6884d85ceb3f339ed");
+    assert_eq!(sodium.size, 6);
+
+    // Players only get the jars in mods/, not the configs.
+    dowload_mods(DEFAULT_INSTANCE_ID).await.unwrap();
+    let mods_directory = get_minecraft_canada_directory().join("mods");
+    assert!(mods_directory.join("lithium.jar").exists());
+    assert!(!mods_directory.join("config").exists());
+
+    // The next publish only sends what changed.
+    write(&root, "mods/lithium.jar", b"lithium 2");
+    fs::remove_file(root.join("mods/sodium.jar")).unwrap();
+    let report = publish_pack(&root, false).await.unwrap();
+    assert_eq!(
+        report.uploaded,
+        vec!["pack-mods/lithium.jar", "pack-mods/pack.json"]
+    );
+    assert_eq!(report.deleted, vec!["pack-mods/sodium.jar"]);
+    assert_eq!(report.unchanged, 2);
+    assert_eq!(published_manifest().files.len(), 3);
+
+    let report = publish_pack(&root, false).await.unwrap();
+    assert!(report.uploaded.is_empty());
+    assert!(report.deleted.is_empty());
+}
+
+#[tokio::test]
+async fn publish_requires_the_mods_and_resource_packs_folders() {
+    setup();
+    let root = temp_directory("publish-incomplete");
+    write(&root, "mods/sodium.jar", b"sodium");
+
+    let result = publish_pack(&root, true).await;
+    assert!(matches!(result, Err(InstallError::InvalidData(_))));
+}
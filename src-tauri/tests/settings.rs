@@ -0,0 +1,128 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::fs;
+
+use canadadowloader_lib::settings::{
+    get_launcher_directory, load_settings, store_settings, take_settings_warning, SETTINGS_VERSION,
+};
+use common::lock;
+use serde_json::json;
+
+fn write_settings(settings: serde_json::Value) {
+    fs::write(
+        get_launcher_directory().join("settings.json"),
+        settings.to_string(),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn malformed_memory_settings_keep_the_rest_of_the_settings() {
+    let _lock = lock().await;
+
+    // A hand-edited memory object without the heap size gets the recommended one.
+    write_settings(json!({
+        "version": 1,
+        "launcher_path": "/opt/minecraft-launcher",
+        "instances": { "default": { "memory": { "min_memory_mb": 1024 } } },
+    }));
+    let settings = load_settings();
+    assert_eq!(settings.launcher_path(), Some("/opt/minecraft-launcher"));
+    let memory = settings.instance().memory.unwrap();
+    assert_eq!(memory.min_memory_mb, Some(1024));
+    assert!(memory.max_memory_mb >= 1024);
+
+    // An unreadable one is dropped on its own.
+    write_settings(json!({
+        "version": 1,
+        "launcher_path": "/opt/minecraft-launcher",
+        "instances": {
+            "default": { "memory": { "max_memory_mb": "lots" }, "mod_loader": "fabric" },
+        },
+    }));
+    let settings = load_settings();
+    assert_eq!(settings.launcher_path(), Some("/opt/minecraft-launcher"));
+    assert_eq!(settings.instance().mod_loader, "fabric");
+    assert!(settings.instance().memory.is_none());
+}
+
+#[tokio::test]
+async fn settings_from_a_newer_launcher_are_never_overwritten() {
+    let _lock = lock().await;
+    let newer = json!({
+        "version": SETTINGS_VERSION + 1,
+        "launcher_path": "/opt/minecraft-launcher",
+        "future_option": true,
+    });
+    write_settings(newer.clone());
+
+    let settings = load_settings();
+    assert_eq!(settings.version, SETTINGS_VERSION + 1);
+    assert_eq!(settings.launcher_path(), Some("/opt/minecraft-launcher"));
+    assert!(store_settings(&settings).is_err());
+    let saved: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(get_launcher_directory().join("settings.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(saved, newer);
+}
+
+#[tokio::test]
+async fn settings_without_a_version_become_the_default_instance() {
+    let _lock = lock().await;
+    write_settings(json!({
+        "launcher_path": "/opt/minecraft-launcher",
+        "minecraft_version": "1.20.1",
+        "mod_loader": "forge",
+        "mod_loader_version": "47.2.0",
+        "resolution": { "width": 1280, "height": 720 },
+    }));
+
+    let settings = load_settings();
+    assert_eq!(settings.version, SETTINGS_VERSION);
+    assert_eq!(settings.selected_instance, "default");
+    assert_eq!(settings.launcher_path(), Some("/opt/minecraft-launcher"));
+    assert_eq!(settings.instances.len(), 1);
+    let instance = settings.instance();
+    assert_eq!(instance.minecraft_version, "1.20.1");
+    assert_eq!(instance.mod_loader, "forge");
+    assert_eq!(instance.mod_loader_version, "47.2.0");
+    let resolution = instance.resolution.unwrap();
+    assert_eq!((resolution.width, resolution.height), (1280, 720));
+}
+
+#[tokio::test]
+async fn corrupt_settings_are_set_aside_and_reported() {
+    let _lock = lock().await;
+    take_settings_warning();
+    let directory = get_launcher_directory();
+    for entry in fs::read_dir(&directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.to_string_lossy().contains("settings.json.corrupt-") {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    fs::write(directory.join("settings.json"), "{\"version\": 1, \"instan").unwrap();
+
+    let settings = load_settings();
+    assert_eq!(settings.launcher_path(), None);
+    assert!(settings.instances.is_empty());
+    assert!(!directory.join("settings.json").exists());
+    let corrupt: Vec<_> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().contains("settings.json.corrupt-"))
+        .collect();
+    assert_eq!(corrupt.len(), 1);
+    assert_eq!(
+        fs::read_to_string(&corrupt[0]).unwrap(),
+        "{\"version\": 1, \"instan"
+    );
+
+    // The problem is shown once.
+    let warning = take_settings_warning().unwrap();
+    assert!(warning.contains(&corrupt[0].display().to_string()));
+    assert_eq!(take_settings_warning(), None);
+}
@@ -0,0 +1,141 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::fs;
+
+use aws_sdk_s3::{
+    config::{Credentials, Region},
+    Client, Config,
+};
+use canadadowloader_lib::dowloader::{
+    dowload_mods, get_minecraft_canada_directory, sync_files, DEFAULT_INSTANCE_ID,
+};
+use common::{setup, temp_directory, MockResponse, MODS_BUCKET, RESOURCE_PACKS_BUCKET};
+
+fn s3_client(url: &str) -> Client {
+    let config = Config::builder()
+        .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+        .region(Region::new("us-east-1"))
+        .endpoint_url(url)
+        .force_path_style(true)
+        .behavior_version_latest()
+        .build();
+    Client::from_conf(config)
+}
+
+#[tokio::test]
+async fn sync_downloads_the_bucket_and_removes_stale_files() {
+    let server = setup();
+    server.bucket(
+        "sync-new",
+        &[("a.jar", b"aaa"), ("b.jar", b"bbbb"), ("pack.json", b"{}")],
+    );
+    let directory = temp_directory("sync-new");
+    fs::write(directory.join("old.jar"), b"old").unwrap();
+
+    sync_files(&s3_client(&server.url), "sync-new", directory.clone(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(fs::read(directory.join("a.jar")).unwrap(), b"aaa");
+    assert_eq!(fs::read(directory.join("b.jar")).unwrap(), b"bbbb");
+    assert!(!directory.join("old.jar").exists());
+    assert!(!directory.join("pack.json").exists());
+}
+
+#[tokio::test]
+async fn sync_skips_unchanged_files() {
+    let server = setup();
+    server.bucket("sync-unchanged", &[("a.jar", b"aaa")]);
+    let directory = temp_directory("sync-unchanged");
+    let client = s3_client(&server.url);
+
+    sync_files(&client, "sync-unchanged", directory.clone(), None)
+        .await
+        .unwrap();
+    sync_files(&client, "sync-unchanged", directory.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(server.hits("/sync-unchanged/a.jar"), 1);
+
+    // A size change is picked up even though the remote file looks older.
+    server.bucket("sync-unchanged", &[("a.jar", b"aaaa")]);
+    sync_files(&client, "sync-unchanged", directory.clone(), None)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(directory.join("a.jar")).unwrap(), b"aaaa");
+}
+
+#[tokio::test]
+async fn sync_fails_when_the_bucket_cannot_be_listed() {
+    let server = setup();
+    server.route("/sync-broken", MockResponse::status(403));
+    let directory = temp_directory("sync-broken");
+
+    let result = sync_files(&s3_client(&server.url), "sync-broken", directory, None).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn dowload_mods_syncs_both_buckets_and_reads_the_pack_manifest() {
+    let server = setup();
+    let pack = serde_json::json!({
+        "servers": [{ "name": "Canada", "ip": "mc.example.com" }],
+        "resource_packs": ["faithful.zip"],
+    });
+    server.bucket(
+        MODS_BUCKET,
+        &[
+            ("sodium.jar", b"sodium"),
+            ("pack.json", pack.to_string().as_bytes()),
+        ],
+    );
+    server.bucket(RESOURCE_PACKS_BUCKET, &[("faithful.zip", b"zip")]);
+
+    let manifest = dowload_mods(DEFAULT_INSTANCE_ID).await.unwrap();
+
+    let instance = get_minecraft_canada_directory();
+    assert_eq!(
+        fs::read(instance.join("mods/sodium.jar")).unwrap(),
+        b"sodium"
+    );
+    assert_eq!(
+        fs::read(instance.join("resourcepacks/faithful.zip")).unwrap(),
+        b"zip"
+    );
+    assert_eq!(manifest.servers.len(), 1);
+    assert_eq!(manifest.servers[0].ip, "mc.example.com");
+    assert_eq!(
+        manifest.resource_packs,
+        Some(vec!["faithful.zip".to_string()])
+    );
+
+    // Without pack.json the defaults are used.
+    server.bucket(MODS_BUCKET, &[("sodium.jar", b"sodium")]);
+    let manifest = dowload_mods(DEFAULT_INSTANCE_ID).await.unwrap();
+    assert!(manifest.servers.is_empty());
+    assert!(manifest.resource_packs.is_none());
+
+    // A malformed pack.json is ignored like a missing one.
+    server.bucket(
+        MODS_BUCKET,
+        &[("sodium.jar", b"sodium"), ("pack.json", b"{ not json")],
+    );
+    let manifest = dowload_mods(DEFAULT_INSTANCE_ID).await.unwrap();
+    assert!(manifest.servers.is_empty());
+
+    // A broken resource packs bucket keeps the packs already synced.
+    server.route(
+        &format!("/{}", RESOURCE_PACKS_BUCKET),
+        MockResponse::status(403),
+    );
+    dowload_mods(DEFAULT_INSTANCE_ID).await.unwrap();
+    assert!(instance.join("resourcepacks/faithful.zip").exists());
+    server.remove(&format!("/{}", RESOURCE_PACKS_BUCKET));
+
+    // A broken mods bucket fails the sync.
+    server.route(&format!("/{}", MODS_BUCKET), MockResponse::status(403));
+    assert!(dowload_mods(DEFAULT_INSTANCE_ID).await.is_err());
+    server.remove(&format!("/{}", MODS_BUCKET));
+}
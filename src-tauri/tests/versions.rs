@@ -0,0 +1,113 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::sync::Arc;
+
+use canadadowloader_lib::{
+    api_error::ApiError,
+    data_structs::{self, VersionType},
+    launcher_core,
+};
+use common::{setup, MockResponse, RecordingSink};
+use serde_json::json;
+
+fn minecraft_version(id: &str, version_type: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "type": version_type,
+        "url": format!("https://piston-meta.mojang.com/v1/packages/{}.json", id),
+        "time": "2024-01-01T00:00:00+00:00",
+        "releaseTime": "2024-01-01T00:00:00+00:00",
+    })
+}
+
+#[tokio::test]
+async fn fabric_versions_come_from_the_configured_meta_server() {
+    let server = setup();
+    server.route(
+        "/fabric-meta/v2/versions/installer",
+        MockResponse::json(json!([
+            { "url": "", "maven": "", "version": "1.0.1", "stable": true },
+            { "url": "", "maven": "", "version": "1.0.0", "stable": false },
+        ])),
+    );
+
+    let sink = Arc::new(RecordingSink::default());
+    let versions = launcher_core::fabric_versions(sink.clone()).await.unwrap();
+    assert_eq!(versions, vec!["1.0.1", "1.0.0"]);
+    assert_eq!(
+        sink.messages(),
+        vec![
+            "Obteniendo versiones de Fabric... 📡",
+            "Versiones de Fabric obtenidas exitosamente! ✅",
+        ]
+    );
+
+    server.route(
+        "/fabric-meta/v2/versions/installer",
+        MockResponse::json(json!({ "not": "a list" })),
+    );
+    let result = data_structs::get_fabric_versions().await;
+    assert!(matches!(result, Err(ApiError::InvalidResponse(_))));
+}
+
+#[tokio::test]
+async fn forge_versions_are_grouped_with_their_promotions() {
+    let server = setup();
+    server.route(
+        "/forge-files/net/minecraftforge/forge/maven-metadata.json",
+        MockResponse::json(json!({ "1.20.1": ["1.20.1-47.1.0", "1.20.1-47.2.0"] })),
+    );
+    server.route(
+        "/forge-files/net/minecraftforge/forge/promotions_slim.json",
+        MockResponse::json(json!({
+            "homepage": "",
+            "promos": { "1.20.1-recommended": "47.1.0", "1.20.1-latest": "47.2.0" },
+        })),
+    );
+
+    let versions = data_structs::get_forge_versions().await.unwrap();
+    let forge = &versions["1.20.1"];
+    assert_eq!(forge.versions, vec!["47.2.0", "47.1.0"]);
+    assert_eq!(forge.recommended.as_deref(), Some("47.1.0"));
+    assert_eq!(forge.latest.as_deref(), Some("47.2.0"));
+
+    server.route(
+        "/forge-files/net/minecraftforge/forge/promotions_slim.json",
+        MockResponse::status(404),
+    );
+    match data_structs::get_forge_versions().await {
+        Err(ApiError::Http { status, .. }) => assert_eq!(status, 404),
+        other => panic!("expected an HTTP error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn minecraft_versions_keep_only_releases_and_retry_server_errors() {
+    let server = setup();
+    server.route_sequence(
+        "/mojang-meta/mc/game/version_manifest.json",
+        vec![
+            MockResponse::status(503),
+            MockResponse::json(json!({
+                "latest": { "release": "1.21", "snapshot": "24w01a" },
+                "versions": [
+                    minecraft_version("24w01a", "snapshot"),
+                    minecraft_version("1.21", "release"),
+                    minecraft_version("b1.7", "old_beta"),
+                ],
+            })),
+        ],
+    );
+
+    let manifest = data_structs::get_minecraft_manifest().await.unwrap();
+    let releases = data_structs::filter_minecraft_manifest(manifest, Some(&[VersionType::Release]));
+    let versions: Vec<_> = releases
+        .versions
+        .iter()
+        .map(|version| &version.id)
+        .collect();
+    assert_eq!(versions, vec!["1.21"]);
+    assert_eq!(server.hits("/mojang-meta/mc/game/version_manifest.json"), 2);
+}
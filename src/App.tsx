@@ -7,14 +7,27 @@ function App() {
   const [ver, setVer] = useState("0.0.1");
   const [mcVersions, setMcVersions] = useState<string[]>([]);
   const [selectedMcVersion, setSelectedMcVersion] = useState<string>("");
-  const [forgeVer, setForgeVer] = useState<Record<string, string>>({});
-  const [parsedForgeVer, setParsedForgeVer] = useState<Record<string, string>>({});
+  const [showSnapshots, setShowSnapshots] = useState(false);
+  const [forgeVer, setForgeVer] = useState<Record<string, ForgeVersions>>({});
   const [fabricVer, setFabricVer] = useState<string[]>([]);
-  const [selectedMod, setSelectedMod] = useState<"forge" | "fabric">("forge");
+  const [selectedMod, setSelectedMod] = useState<"forge" | "fabric" | "vanilla">("forge");
   const [versions, setVersions] = useState<string[]>([]);
   const [selectedModVersion, setSelectedModVersion] = useState<string>("");
   const [logs, setLogs] = useState<string[]>([]);
   const [isDownloading, setIsDownloading] = useState(false);
+  const [minimizeToTray, setMinimizeToTray] = useState(false);
+  const [offlineMode, setOfflineMode] = useState(false);
+  const [launcherClosed, setLauncherClosed] = useState<LauncherClosedPayload | null>(null);
+  const [launcherPath, setLauncherPath] = useState<string>("");
+  const [launcherStatus, setLauncherStatus] = useState<string>("");
+  const [proxy, setProxy] = useState<string>("");
+  const [proxyStatus, setProxyStatus] = useState<string>("");
+  const [memoryInfo, setMemoryInfo] = useState<MemoryInfo | null>(null);
+  const [memorySettings, setMemorySettings] = useState<MemorySettings | null>(null);
+  const [memoryStatus, setMemoryStatus] = useState<string>("");
+  const [resolution, setResolution] = useState<ProfileResolution | null>(null);
+  const [java, setJava] = useState<JavaSelection>({ kind: "launcher" });
+  const [profileStatus, setProfileStatus] = useState<string>("");
 
   const logContainerRef = useRef<HTMLDivElement>(null);
 
@@ -38,35 +51,123 @@ function App() {
 
   useEffect(() => {
     fetchVersion();
-    fetchMcVersions();
     fetchFabricVersions();
     fetchForgeVersions();
     get_saved_data();
+    fetchMinecraftLauncher();
+    fetchMemorySettings();
     const randomImage = wallpapers[Math.floor(Math.random() * wallpapers.length)];
     document.body.style.background = `url(${randomImage}) no-repeat center center fixed`;
     document.body.style.backgroundSize = "cover";
   }, []);
 
+  interface MinecraftVersion {
+    id: string;
+    type: "release" | "snapshot" | "old_beta" | "old_alpha" | "unknown";
+    url: string;
+    time: string;
+    releaseTime: string;
+  }
+
+  interface MinecraftManifest {
+    latest: { release: string; snapshot: string };
+    versions: MinecraftVersion[];
+  }
+
+  interface ForgeVersions {
+    versions: string[];
+    recommended: string | null;
+    latest: string | null;
+  }
+
+  interface ApiError {
+    kind: "offline" | "timeout" | "network" | "http" | "invalid_response";
+    message: string;
+    status: number | null;
+  }
+
+  interface InstallError {
+    kind: "network" | "java" | "installer_exit" | "filesystem" | "profile_write" | "invalid_data";
+    message: string;
+    exit_code: number | null;
+  }
+
+  interface LauncherClosedPayload {
+    exit_code: number | null;
+    crash_report: string | null;
+  }
+
   interface LogEventPayload {
     message: string;
   }
 
-  interface Data {
+  interface InstanceSettings {
     minecraft_version: string;
     mod_loader: string;
     mod_loader_version: string;
+    memory: MemorySettings | null;
+    java: JavaSelection;
+    resolution: ProfileResolution | null;
+  }
+
+  interface Settings {
+    version: number;
+    selected_instance: string;
+    instances: Record<string, InstanceSettings>;
+    launcher_path: string | null;
+    language: string;
+    channel: "stable" | "beta";
+    theme: "system" | "light" | "dark";
+    offline_mode: boolean;
+    proxy: string | null;
+  }
+
+  interface ProfileResolution {
+    width: number;
+    height: number;
+  }
+
+  type JavaSelection = { kind: "launcher" } | { kind: "managed" } | { kind: "custom"; path: string };
+
+  interface MemoryInfo {
+    total_memory_mb: number;
+    recommended_memory_mb: number;
+  }
+
+  interface MemorySettings {
+    min_memory_mb: number | null;
+    max_memory_mb: number;
+    preset: "none" | "aikar";
+    extra_args: string;
+  }
+
+  interface MinecraftLauncher {
+    source: string;
+    program: string;
+    args: string[];
   }
   
   function get_saved_data() {
-    invoke<Data>("get_data")
-      .then((data) => {
-        if (data) {
-          setSelectedMcVersion(data.minecraft_version);
-          setSelectedMod(data.mod_loader as "forge" | "fabric");
-          setSelectedModVersion(data.mod_loader_version);
+    invoke<Settings>("get_settings")
+      .then((settings) => {
+        setLauncherPath(settings.launcher_path ?? "");
+        setOfflineMode(settings.offline_mode);
+        setProxy(settings.proxy ?? "");
+        const instance = settings.instances[settings.selected_instance];
+        if (instance) {
+          setSelectedMcVersion(instance.minecraft_version);
+          setSelectedMod((instance.mod_loader || "forge") as "forge" | "fabric" | "vanilla");
+          setSelectedModVersion(instance.mod_loader_version);
+          setResolution(instance.resolution);
+          setJava(instance.java);
         }
       })
-      .catch((error) => console.error("Error fetching saved data:", error));
+      .catch((error) => console.error("Error fetching saved data:", error))
+      .finally(() =>
+        invoke<string | null>("get_settings_warning").then((warning) => {
+          if (warning) setLogs((prevLogs) => [...prevLogs, `⚠️ ${warning}`]);
+        })
+      );
   }
   
   useEffect(() => {
@@ -76,9 +177,14 @@ function App() {
       setLogs((prevLogs) => [...prevLogs, message]);
     });
 
+    const unsubscribeClosed = listen("minecraft-launcher-closed", (event: { payload: LauncherClosedPayload }) => {
+      setLauncherClosed(event.payload);
+    });
+
     // Cleanup listener on component unmount
     return () => {
       unsubscribe.then((unsub) => unsub());
+      unsubscribeClosed.then((unsub) => unsub());
     };
   }, []);
 
@@ -92,39 +198,44 @@ function App() {
     invoke<string>("get_version").then((version) => setVer(version));
   }
 
-  function fetchMcVersions() {
-    invoke<string[]>("get_minecraft_versions")
-      .then((mc_versions) => setMcVersions(Array.isArray(mc_versions) ? mc_versions : []))
-      .catch((error) => console.error("Error al obtener versiones de Minecraft:", error));
+  function fetchMcVersions(withSnapshots: boolean) {
+    invoke<MinecraftManifest>("get_minecraft_manifest", {
+      versionTypes: withSnapshots ? ["release", "snapshot"] : ["release"],
+    })
+      .then((manifest) => setMcVersions(manifest.versions.map((version) => version.id)))
+      .catch((error: ApiError) => {
+        setMcVersions([]);
+        setLogs((prevLogs) => [...prevLogs, `❌ Error al obtener versiones de Minecraft: ${error.message}`]);
+      });
   }
 
+  useEffect(() => {
+    fetchMcVersions(showSnapshots);
+  }, [showSnapshots]);
+
   function fetchForgeVersions() {
-    invoke<Record<string, string>>("get_forge_versions")
+    invoke<Record<string, ForgeVersions>>("get_forge_versions")
       .then((forge_versions) => setForgeVer(forge_versions))
-      .catch((error) => console.error("Error al obtener versiones de Forge:", error));
+      .catch((error: ApiError) => {
+        setForgeVer({});
+        console.error("Error al obtener versiones de Forge:", error.message);
+      });
   }
 
   function fetchFabricVersions() {
     invoke<string[]>("get_fabric_versions")
-      .then((fabric_versions) => setFabricVer(Array.isArray(fabric_versions) ? fabric_versions : []))
-      .catch((error) => console.error("Error al obtener versiones de Fabric:", error));
+      .then((fabric_versions) => setFabricVer(fabric_versions))
+      .catch((error: ApiError) => {
+        setFabricVer([]);
+        console.error("Error al obtener versiones de Fabric:", error.message);
+      });
   }
 
   useEffect(() => {
-    const parsed: Record<string, string> = {};
-    Object.entries(forgeVer).forEach(([key, version]) => {
-      const mcVersion = key.replace("-latest", "").replace("-recommended", "");
-      if (!parsed[mcVersion] || key.endsWith("-latest")) {
-        parsed[mcVersion] = version;
-      }
-    });
-    setParsedForgeVer(parsed);
-  }, [forgeVer]);
-
-  useEffect(() => {
-    if (selectedMod === "forge" && selectedMcVersion in parsedForgeVer) {
-      setVersions([parsedForgeVer[selectedMcVersion]]);
-      setSelectedModVersion(parsedForgeVer[selectedMcVersion]);
+    if (selectedMod === "forge" && selectedMcVersion in forgeVer) {
+      const forge = forgeVer[selectedMcVersion];
+      setVersions(forge.versions);
+      setSelectedModVersion(forge.recommended || forge.latest || forge.versions[0] || "");
     } else if (selectedMod === "fabric") {
       setVersions(fabricVer);
       setSelectedModVersion(fabricVer[0] || "");
@@ -132,7 +243,75 @@ function App() {
       setVersions([]);
       setSelectedModVersion("");
     }
-  }, [selectedMod, selectedMcVersion, parsedForgeVer, fabricVer]);
+  }, [selectedMod, selectedMcVersion, forgeVer, fabricVer]);
+
+  function versionLabel(version: string) {
+    const forge = forgeVer[selectedMcVersion];
+    if (selectedMod !== "forge" || !forge) return version;
+    if (version === forge.recommended) return `${version} (recomendada)`;
+    if (version === forge.latest) return `${version} (última)`;
+    return version;
+  }
+
+  function fetchMinecraftLauncher() {
+    invoke<MinecraftLauncher>("get_minecraft_launcher")
+      .then((launcher) => setLauncherStatus(`✅ ${launcher.program}`))
+      .catch((error) => setLauncherStatus(`❌ ${error}`));
+  }
+
+  function saveLauncherPath() {
+    invoke<MinecraftLauncher>("set_minecraft_launcher_path", { path: launcherPath || null })
+      .then((launcher) => setLauncherStatus(`✅ ${launcher.program}`))
+      .catch((error) => setLauncherStatus(`❌ ${error}`));
+  }
+
+  function saveProxy() {
+    invoke("set_proxy", { proxy: proxy || null })
+      .then(() => setProxyStatus("✅ Guardado"))
+      .catch((error) => setProxyStatus(`❌ ${error}`));
+  }
+
+  function fetchMemorySettings() {
+    invoke<MemoryInfo>("get_memory_info").then((info) => setMemoryInfo(info));
+    invoke<MemorySettings>("get_memory_settings").then((settings) => setMemorySettings(settings));
+  }
+
+  function saveMemorySettings(settings: MemorySettings | null) {
+    invoke<string>("set_memory_settings", { memory: settings })
+      .then((javaArgs) => {
+        setMemoryStatus(`✅ ${javaArgs}`);
+        if (!settings) fetchMemorySettings();
+      })
+      .catch((error) => setMemoryStatus(`❌ ${error}`));
+  }
+
+  function saveProfileSettings() {
+    invoke("set_profile_settings", { resolution, java })
+      .then(() => setProfileStatus("✅ Guardado"))
+      .catch((error) => setProfileStatus(`❌ ${error}`));
+  }
+
+  function toggleOfflineMode(enabled: boolean) {
+    invoke("set_offline_mode", { enabled })
+      .then(() => {
+        setOfflineMode(enabled);
+        fetchMcVersions(showSnapshots);
+        fetchFabricVersions();
+        fetchForgeVersions();
+      })
+      .catch((error) => setLogs((prevLogs) => [...prevLogs, `❌ ${error}`]));
+  }
+
+  function relaunchMinecraft() {
+    setLauncherClosed(null);
+    invoke("relaunch_minecraft", { postLaunch: minimizeToTray ? "minimize_to_tray" : "stay_open" })
+      .catch((error) => setLogs((prevLogs) => [...prevLogs, `❌ ${error}`]));
+  }
+
+  function openCrashReport(path: string) {
+    invoke("open_crash_report", { path })
+      .catch((error) => setLogs((prevLogs) => [...prevLogs, `❌ ${error}`]));
+  }
 
   function handleSubmit(event: React.FormEvent) {
     invoke("save_data", {
@@ -141,19 +320,21 @@ function App() {
       modLoaderVersion: selectedModVersion
     })
     event.preventDefault();
-    if (!selectedMcVersion || !selectedModVersion) return;
+    if (!selectedMcVersion || (!selectedModVersion && selectedMod !== "vanilla")) return;
 
     setIsDownloading(true);
+    setLauncherClosed(null);
 
     invoke("start_downloading", {
       minecraftVersion: selectedMcVersion,
       modTypeStr: selectedMod,
       modVersion: selectedModVersion,
+      postLaunch: minimizeToTray ? "minimize_to_tray" : "stay_open",
     })
       .then(() => setLogs((prevLogs) => [...prevLogs, "✅ Instalación iniciada..."]))
-      .catch((error) => {
+      .catch((error: InstallError) => {
         console.error("Error al instalar el mod:", error);
-        setLogs((prevLogs) => [...prevLogs, "❌ Error al iniciar la instalación."]);
+        setLogs((prevLogs) => [...prevLogs, `❌ ${error.message ?? "Error al iniciar la instalación."}`]);
       })
       .finally(() => setIsDownloading(false));
   }
@@ -168,26 +349,157 @@ function App() {
             <option key={version} value={version}>{version}</option>
           ))}
         </select>
+        <label>
+          <input type="checkbox" checked={showSnapshots} onChange={(e) => setShowSnapshots(e.target.checked)} />
+          Mostrar snapshots
+        </label>
 
         <label>Selecciona el Mod loader:</label>
-        <select value={selectedMod} onChange={(e) => setSelectedMod(e.target.value as "forge" | "fabric")}>
+        <select value={selectedMod} onChange={(e) => setSelectedMod(e.target.value as "forge" | "fabric" | "vanilla")}>
           <option value="forge">Forge</option>
           <option value="fabric">Fabric</option>
+          <option value="vanilla">Vanilla</option>
         </select>
 
         <label>Selecciona la versión del Mod Loader:</label>
         <select value={selectedModVersion} onChange={(e) => setSelectedModVersion(e.target.value)} disabled={versions.length === 0}>
           <option value="">Seleccionar versión del mod</option>
           {versions.map((version) => (
-            <option key={version} value={version}>{version}</option>
+            <option key={version} value={version}>{versionLabel(version)}</option>
           ))}
         </select>
 
-        <button type="submit" disabled={!selectedMcVersion || !selectedModVersion || isDownloading}>
+        <button type="submit" disabled={!selectedMcVersion || (!selectedModVersion && selectedMod !== "vanilla") || isDownloading}>
           {isDownloading ? "Iniciando..." : "Iniciar"}
         </button>
+        <label>
+          <input type="checkbox" checked={minimizeToTray} onChange={(e) => setMinimizeToTray(e.target.checked)} />
+          Minimizar a la bandeja al iniciar Minecraft
+        </label>
+        <label>
+          <input type="checkbox" checked={offlineMode} onChange={(e) => toggleOfflineMode(e.target.checked)} />
+          Modo sin conexión (jugar con lo ya instalado)
+        </label>
       </form>
 
+      {launcherClosed && (
+        <div className="minecraft-exit">
+          <p>
+            {launcherClosed.exit_code !== null && launcherClosed.exit_code !== 0
+              ? `❌ El Minecraft Launcher terminó con código ${launcherClosed.exit_code}.`
+              : "El Minecraft Launcher se cerró."}
+            {launcherClosed.crash_report && " ❌ Minecraft dejó un informe de error."}
+          </p>
+          <button type="button" onClick={relaunchMinecraft}>Volver a abrir</button>
+          {launcherClosed.crash_report && (
+            <button type="button" onClick={() => openCrashReport(launcherClosed.crash_report!)}>
+              Abrir informe de error
+            </button>
+          )}
+        </div>
+      )}
+
+      <div className="launcher-path">
+        <label>Ruta del Minecraft Launcher (vacío para detectarlo):</label>
+        <input value={launcherPath} onChange={(e) => setLauncherPath(e.target.value)} />
+        <button type="button" onClick={saveLauncherPath}>Guardar</button>
+        <p>{launcherStatus}</p>
+      </div>
+
+      <div className="proxy">
+        <label>Proxy (http://host:puerto o https://host:puerto, vacío para no usarlo):</label>
+        <input value={proxy} onChange={(e) => setProxy(e.target.value)} />
+        <button type="button" onClick={saveProxy}>Guardar</button>
+        <p>{proxyStatus}</p>
+      </div>
+
+      {memorySettings && (
+        <div className="memory-settings">
+          <label>
+            Memoria máxima (MB)
+            {memoryInfo && ` — sistema: ${memoryInfo.total_memory_mb} MB, recomendada: ${memoryInfo.recommended_memory_mb} MB`}:
+          </label>
+          <input
+            type="number"
+            min={1024}
+            step={512}
+            value={memorySettings.max_memory_mb}
+            onChange={(e) => setMemorySettings({ ...memorySettings, max_memory_mb: Number(e.target.value) })}
+          />
+          <label>Memoria mínima (MB, vacío para no fijarla):</label>
+          <input
+            type="number"
+            min={0}
+            step={512}
+            value={memorySettings.min_memory_mb ?? ""}
+            onChange={(e) =>
+              setMemorySettings({ ...memorySettings, min_memory_mb: e.target.value ? Number(e.target.value) : null })
+            }
+          />
+          <label>Flags de la JVM:</label>
+          <select
+            value={memorySettings.preset}
+            onChange={(e) => setMemorySettings({ ...memorySettings, preset: e.target.value as "none" | "aikar" })}
+          >
+            <option value="none">Ninguno</option>
+            <option value="aikar">Aikar</option>
+          </select>
+          <label>Argumentos extra de Java:</label>
+          <input
+            value={memorySettings.extra_args}
+            onChange={(e) => setMemorySettings({ ...memorySettings, extra_args: e.target.value })}
+          />
+          <button type="button" onClick={() => saveMemorySettings(memorySettings)}>Guardar</button>
+          <button type="button" onClick={() => saveMemorySettings(null)}>Usar recomendada</button>
+          <p>{memoryStatus}</p>
+        </div>
+      )}
+
+      <div className="profile-settings">
+        <label>
+          <input
+            type="checkbox"
+            checked={resolution !== null}
+            onChange={(e) => setResolution(e.target.checked ? { width: 1280, height: 720 } : null)}
+          />
+          Fijar resolución de la ventana
+        </label>
+        {resolution && (
+          <div>
+            <input
+              type="number"
+              min={1}
+              value={resolution.width}
+              onChange={(e) => setResolution({ ...resolution, width: Number(e.target.value) })}
+            />
+            x
+            <input
+              type="number"
+              min={1}
+              value={resolution.height}
+              onChange={(e) => setResolution({ ...resolution, height: Number(e.target.value) })}
+            />
+          </div>
+        )}
+        <label>Java:</label>
+        <select
+          value={java.kind}
+          onChange={(e) => {
+            const kind = e.target.value as JavaSelection["kind"];
+            setJava(kind === "custom" ? { kind, path: "" } : { kind });
+          }}
+        >
+          <option value="launcher">El del Minecraft Launcher</option>
+          <option value="managed">Descargado por Canada Launcher</option>
+          <option value="custom">Personalizado</option>
+        </select>
+        {java.kind === "custom" && (
+          <input value={java.path} onChange={(e) => setJava({ kind: "custom", path: e.target.value })} />
+        )}
+        <button type="button" onClick={saveProfileSettings}>Guardar</button>
+        <p>{profileStatus}</p>
+      </div>
+
       <h2>Registro de instalación:</h2>
       <div className="log-box" ref={logContainerRef} style={{ maxHeight: "300px", overflowY: "auto" }}>
         {logs.length === 0 ? (